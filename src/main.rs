@@ -1,9 +1,16 @@
 // #![windows_subsystem = "windows"]
 
-use std::sync::{Arc, RwLock, mpsc};
+use std::{sync::{Arc, RwLock, mpsc}, env};
 
 use draw::create_window;
-use snaek::draw::Frontend;
+use snaek::{
+    controller::{Controller, KeyboardController, ReplayController, RivalController},
+    draw::Frontend,
+    levels::{LevelState, LEVELS},
+    net::{NetController, NetGame, NetLink},
+    replay::{Replay, ReplayRecorder},
+    types::{GameState, MAX_PLAYERS},
+};
 
 mod global;
 mod draw;
@@ -14,7 +21,62 @@ mod text;
 fn main() {
     // start_classic();
     // start_snaek_piston();
-    start_snaek::<snaek::draw::Sdl2Frontend>();
+    let mut args: Vec<String> = env::args().collect();
+    let level_index = match take_level_arg(&mut args) {
+        Ok(level_index) => level_index,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    match args.get(1).map(String::as_str) {
+        Some("--replay") => {
+            let path = args.get(2).expect("Usage: snaek --replay <file>");
+            start_snaek_replay::<snaek::draw::Sdl2Frontend>(path);
+        }
+        Some("--load") => {
+            let path = args.get(2).expect("Usage: snaek --load <file>");
+            start_snaek_loaded::<snaek::draw::Sdl2Frontend>(path);
+        }
+        Some("--headless") => {
+            let usage = "Usage: snaek --headless <ticks> [seed] [--bot] [--level <n>]";
+            let ticks = args.get(2).expect(usage).parse().expect(usage);
+            let bot = args.iter().skip(3).any(|arg| arg == "--bot");
+            let seed = args.get(3).filter(|arg| *arg != "--bot").map(|seed| seed.parse().expect(usage));
+            snaek::headless::run_headless(ticks, seed, level_index, bot);
+        }
+        Some("--host") => {
+            let usage = format!("Usage: snaek --host <port> [players, 2 to {}] [--level <n>]", MAX_PLAYERS);
+            let port = args.get(2).expect(&usage).parse().expect(&usage);
+            let num_players = args.get(3).map_or(2, |n| n.parse().expect(&usage));
+            // Only as many players as there are snake colours and scoreboard spaces
+            if !(2..=MAX_PLAYERS).contains(&num_players) {
+                println!("A network game needs between 2 and {} players", MAX_PLAYERS);
+                return;
+            }
+            start_snaek_host::<snaek::draw::Sdl2Frontend>(port, level_index, num_players);
+        }
+        Some("--join") => {
+            let addr = args.get(2).expect("Usage: snaek --join <host:port>");
+            start_snaek_join::<snaek::draw::Sdl2Frontend>(addr);
+        }
+        Some("--two-player") => start_snaek::<snaek::draw::Sdl2Frontend>(level_index, 2),
+        _ => start_snaek::<snaek::draw::Sdl2Frontend>(level_index, 1),
+    }
+}
+
+/// Takes `--level <n>` (counting from 1) out of `args` and returns the level's index
+fn take_level_arg(args: &mut Vec<String>) -> Result<usize, String> {
+    let Some(i) = args.iter().position(|arg| arg == "--level") else {
+        return Ok(0);
+    };
+    let usage = format!("Usage: --level <n>, where n is 1 to {}", LEVELS.len());
+    let level = args.get(i + 1).and_then(|n| n.parse::<usize>().ok()).ok_or_else(|| usage.clone())?;
+    if !(1..=LEVELS.len()).contains(&level) {
+        return Err(usage);
+    }
+    args.drain(i..i + 2);
+    Ok(level - 1)
 }
 
 
@@ -30,14 +92,89 @@ fn start_classic() {
     classic::draw::window_loop(&mut window, board, tx);
 }
 
-fn start_snaek<F: Frontend>() {
+fn start_snaek<F: Frontend>(level_index: usize, num_players: usize) {
+    let f = F::new((global::W_WIDTH, 800));
+
+    let (s, l) = snaek::logic::reset(level_index, num_players);
+    let recorder = match ReplayRecorder::create_in_replay_dir(s.seed, s.level.index, s.players.len()) {
+        Ok(recorder) => Some(recorder),
+        Err(err) => {
+            println!("Unable to record replay: {}", err);
+            None
+        }
+    };
+
+    let rivals = RivalController::new(s.seed);
+    let s = Arc::new(RwLock::new(s));
+    let (tx, rx) = mpsc::channel();
+    let controllers: Vec<Box<dyn Controller>> = vec![Box::new(KeyboardController::new(rx)), Box::new(rivals)];
+    snaek::logic::spawn_logic_thread(s.clone(), l, controllers, recorder);
+
+    snaek::draw::window_loop(f, s, tx);
+}
+
+fn start_snaek_loaded<F: Frontend>(path: &str) {
+    let (s, l) = snaek::save::load_game(path).expect("Unable to load save");
+    let f = F::new((global::W_WIDTH, 800));
+
+    let rivals = RivalController::new(s.seed);
+    let s = Arc::new(RwLock::new(s));
+    let (tx, rx) = mpsc::channel();
+    // A loaded game can't be replayed from its seed, so it isn't recorded
+    let controllers: Vec<Box<dyn Controller>> = vec![Box::new(KeyboardController::new(rx)), Box::new(rivals)];
+    snaek::logic::spawn_logic_thread(s.clone(), l, controllers, None);
+
+    snaek::draw::window_loop(f, s, tx);
+}
+
+fn start_snaek_host<F: Frontend>(port: u16, level_index: usize, num_players: usize) {
+    let (s, l) = snaek::logic::reset(level_index, num_players);
+    let game = NetGame { seed: s.seed, level_index: s.level.index, num_players: s.players.len() };
+    let link = snaek::net::host(port, &game).expect("Unable to host game");
+    start_snaek_networked::<F>(s, l, link);
+}
+
+fn start_snaek_join<F: Frontend>(addr: &str) {
+    let (link, game) = snaek::net::join(addr).expect("Unable to join game");
+    let (s, l) = snaek::logic::reset_with_seed(game.seed, game.level_index, game.num_players);
+    start_snaek_networked::<F>(s, l, link);
+}
+
+fn start_snaek_networked<F: Frontend>(s: GameState, l: Box<dyn LevelState>, link: NetLink) {
     let f = F::new((global::W_WIDTH, 800));
 
-    let (s, l) = snaek::logic::reset();
+    let recorder = match ReplayRecorder::create_in_replay_dir(s.seed, s.level.index, s.players.len()) {
+        Ok(recorder) => Some(recorder),
+        Err(err) => {
+            println!("Unable to record replay: {}", err);
+            None
+        }
+    };
 
+    // Every instance steers the rivals the same way from the same seed, so their turns aren't sent
+    let rivals = RivalController::new(s.seed);
     let s = Arc::new(RwLock::new(s));
     let (tx, rx) = mpsc::channel();
-    snaek::logic::spawn_logic_thread(s.clone(), l, rx);
+    let controllers: Vec<Box<dyn Controller>> = vec![
+        Box::new(NetController::new(Box::new(KeyboardController::new(rx)), link)),
+        Box::new(rivals),
+    ];
+    snaek::logic::spawn_logic_thread(s.clone(), l, controllers, recorder);
 
     snaek::draw::window_loop(f, s, tx);
 }
+
+fn start_snaek_replay<F: Frontend>(path: &str) {
+    let replay = Replay::load(path).expect("Unable to load replay");
+    let f = F::new((global::W_WIDTH, 800));
+
+    let (s, l) = snaek::logic::reset_with_seed(replay.seed, replay.level_index, replay.num_players);
+
+    let s = Arc::new(RwLock::new(s));
+    let controllers: Vec<Box<dyn Controller>> = vec![Box::new(ReplayController::new(replay))];
+    snaek::logic::spawn_logic_thread(s.clone(), l, controllers, None);
+
+    // The frontend's actions are not fed to the game during playback
+    let (frontend_tx, _frontend_rx) = mpsc::channel();
+    snaek::draw::window_loop(f, s, frontend_tx);
+}
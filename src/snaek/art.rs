@@ -1,10 +1,14 @@
-use super::types::{Board, CellFloor, CellObject, CellState, Coord, IndicatorType, PowerupType, MAX_SATURATION};
+use rand::Rng;
+
+use super::types::{Board, CellFloor, CellObject, CellState, Coord, IndicatorType, PowerupType, LAVA_MAX_HEAT, MAX_SATURATION};
 use std::mem::discriminant as variant;
 
 use crate::text::{GRIDS, C_WIDTH};
 
 const EXPLOSION_WALK_COUNT: usize = 30;
 const EXPLOSION_WALK_MAX_DIST: usize = 30;
+const CRATER_WALK_COUNT: usize = 30;
+const CRATER_WALK_DIST: usize = 6;
 
 pub trait Fill: Copy {
     fn fill(&self, cell: &mut CellState);
@@ -61,8 +65,8 @@ pub struct PlusWater(pub u8);
 impl Fill for PlusWater {
     fn fill(&self, cell: &mut CellState) {
         match &mut cell.floor {
-            CellFloor::Water { depth } => *depth = depth.saturating_add(self.0),
-            CellFloor::Lava { depth } => {
+            CellFloor::Water { depth, .. } => *depth = depth.saturating_add(self.0),
+            CellFloor::Lava { depth, .. } => {
                 match self.0.cmp(depth) {
                     std::cmp::Ordering::Less => {
                         *depth -= self.0;
@@ -76,7 +80,7 @@ impl Fill for PlusWater {
                     },
                 }
             }
-            CellFloor::Empty => cell.floor = CellFloor::Water { depth: self.0 },
+            CellFloor::Empty => cell.floor = CellFloor::Water { depth: self.0, sediment: 0 },
             _ => {}
         }
     }
@@ -94,22 +98,27 @@ pub struct PlusLava(pub u8);
 impl Fill for PlusLava {
     fn fill(&self, cell: &mut CellState) {
         match &mut cell.floor {
-            CellFloor::Lava { depth } => *depth = depth.saturating_add(self.0),
-            CellFloor::Water { depth } => {
+            CellFloor::Lava { depth, heat } => {
+                *depth = depth.saturating_add(self.0);
+                *heat = LAVA_MAX_HEAT;
+            }
+            CellFloor::Water { depth, sediment } => {
                 match self.0.cmp(depth) {
                     std::cmp::Ordering::Less => {
                         *depth -= self.0;
                     },
                     std::cmp::Ordering::Equal => {
+                        cell.elev = cell.elev.saturating_add(*sediment);
                         cell.floor = CellFloor::Empty;
                     },
                     std::cmp::Ordering::Greater => {
-                        cell.elev = cell.elev.saturating_add(*depth);
+                        cell.elev = cell.elev.saturating_add(*depth).saturating_add(*sediment);
                         *depth = 0;
+                        *sediment = 0;
                     },
                 }
             }
-            CellFloor::Empty => cell.floor = CellFloor::Lava { depth: self.0 },
+            CellFloor::Empty => cell.floor = CellFloor::Lava { depth: self.0, heat: LAVA_MAX_HEAT },
             _ => {}
         }
     }
@@ -123,11 +132,18 @@ impl CellMatch for PlusLava {
     }
 }
 #[derive(Clone, Copy)]
+pub struct MinusElev(pub u8);
+impl Fill for MinusElev {
+    fn fill(&self, cell: &mut CellState) {
+        cell.elev = cell.elev.saturating_sub(self.0);
+    }
+}
+#[derive(Clone, Copy)]
 pub struct PlusSeed(pub u8);
 impl Fill for PlusSeed {
     fn fill(&self, cell: &mut CellState) {
         match &mut cell.floor {
-            CellFloor::Lava { depth: height } | CellFloor::Water { depth: height }  | CellFloor::Seed { height, .. } => *height = height.saturating_add(self.0),
+            CellFloor::Lava { depth: height, .. } | CellFloor::Water { depth: height, .. }  | CellFloor::Seed { height, .. } => *height = height.saturating_add(self.0),
             CellFloor::Empty => cell.floor = CellFloor::Seed { height: self.0, saturation: MAX_SATURATION },
             _ => {}
         }
@@ -205,7 +221,8 @@ pub trait BoardArt {
     fn circle(&mut self, center: impl Into<Coord>, radius: usize, fill: impl Fill);
     fn rect(&mut self, from: impl Into<Coord>, to: impl Into<Coord>, fill: impl Fill);
     fn text(&mut self, text: &str, coord: impl Into<Coord>, fill: impl Fill, empty: impl Fill);
-    fn explosion(&mut self, center: impl Into<Coord>, fill: impl Fill + CellMatch);
+    fn explosion(&mut self, center: impl Into<Coord>, fill: impl Fill + CellMatch, rng: &mut impl Rng);
+    fn crater(&mut self, center: impl Into<Coord>, fill: impl Fill, rng: &mut impl Rng);
 }
 impl<const W: usize, const H: usize> BoardArt for Board<W, H> {
     fn line(&mut self, from: impl Into<Coord>, to: impl Into<Coord>, fill: impl Fill) {
@@ -214,7 +231,7 @@ impl<const W: usize, const H: usize> BoardArt for Board<W, H> {
 
     fn pt(&mut self, pt: impl Into<Coord>, fill: impl Fill) {
         let pt = pt.into();
-        if !pt.in_bounds() { return; }
+        if pt.x >= W || pt.y >= H { return; }
         self.cell_at_mut(pt).update(fill);
     }
 
@@ -238,8 +255,12 @@ impl<const W: usize, const H: usize> BoardArt for Board<W, H> {
         }
     }
 
-    fn explosion(&mut self, center: impl Into<Coord>, fill: impl Fill + CellMatch) {
-        explosion(self, center, fill);
+    fn explosion(&mut self, center: impl Into<Coord>, fill: impl Fill + CellMatch, rng: &mut impl Rng) {
+        explosion(self, center, fill, rng);
+    }
+
+    fn crater(&mut self, center: impl Into<Coord>, fill: impl Fill, rng: &mut impl Rng) {
+        crater(self, center, fill, rng);
     }
 }
 
@@ -265,6 +286,17 @@ pub fn write_letter<
     }
 }
 
+/// The in-bounds board coordinates within `radius` of `center`
+pub fn disk(center: impl Into<Coord>, radius: usize) -> impl Iterator<Item = Coord> {
+    let center = center.into();
+    let r = radius as isize;
+    (-r..=r)
+        .flat_map(move |dy| (-r..=r).map(move |dx| (dx, dy)))
+        .filter(move |(dx, dy)| dx * dx + dy * dy <= r * r)
+        .map(move |(dx, dy)| Coord { x: (center.x as isize + dx) as usize, y: (center.y as isize + dy) as usize })
+        .filter(Coord::in_bounds)
+}
+
 fn lerp(from: usize, to: usize, num: usize, den: usize) -> usize {
     (from * (den - num) + to * num) / den
 }
@@ -381,14 +413,14 @@ fn rect<const W: usize, const H: usize>(board: &mut Board<W, H>, from: impl Into
     }
 }
 
-fn explosion<const W: usize, const H: usize>(board: &mut Board<W, H>, center: impl Into<Coord>, fill: impl Fill + CellMatch) {
+fn explosion<const W: usize, const H: usize>(board: &mut Board<W, H>, center: impl Into<Coord>, fill: impl Fill + CellMatch, rng: &mut impl Rng) {
     let center = center.into();
     for _ in 0..EXPLOSION_WALK_COUNT {
-        walk(board, center, EXPLOSION_WALK_MAX_DIST, fill);
+        walk(board, center, EXPLOSION_WALK_MAX_DIST, fill, rng);
     }
 }
 
-fn walk<const W: usize, const H: usize>(board: &mut Board<W, H>, from: impl Into<Coord>, max_dist: usize, fill: impl Fill + CellMatch) {
+fn walk<const W: usize, const H: usize>(board: &mut Board<W, H>, from: impl Into<Coord>, max_dist: usize, fill: impl Fill + CellMatch, rng: &mut impl Rng) {
     let mut pos = from.into();
     for _ in 0..max_dist {
         if !board.cell_at(pos).vmatches(fill) {
@@ -398,7 +430,27 @@ fn walk<const W: usize, const H: usize>(board: &mut Board<W, H>, from: impl Into
             }
             break;
         }
-        let dir = rand::random();
+        let dir = rng.gen();
+        pos = pos.add_wrapped(dir);
+    }
+}
+
+/// Like `explosion`, but fills every cell along each walk
+fn crater<const W: usize, const H: usize>(board: &mut Board<W, H>, center: impl Into<Coord>, fill: impl Fill, rng: &mut impl Rng) {
+    let center = center.into();
+    for _ in 0..CRATER_WALK_COUNT {
+        trail(board, center, CRATER_WALK_DIST, fill, rng);
+    }
+}
+
+fn trail<const W: usize, const H: usize>(board: &mut Board<W, H>, from: impl Into<Coord>, dist: usize, fill: impl Fill, rng: &mut impl Rng) {
+    let mut pos = from.into();
+    for _ in 0..dist {
+        // Do not overwrite a border
+        if !board.cell_at(pos).vmatches(CellObject::Border) {
+            board.pt(pos, fill);
+        }
+        let dir = rng.gen();
         pos = pos.add_wrapped(dir);
     }
 }
@@ -0,0 +1,127 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{Receiver, TryRecvError},
+};
+
+use rand::SeedableRng;
+
+use super::{
+    logic::UserAction,
+    replay::Replay,
+    rival::choose_dir,
+    types::{GameRng, GameState},
+};
+
+/// A source of actions for the game: a person, a recording or a bot
+pub trait Controller: Send {
+    /// The actions to apply this tick, or `None` once the controller is gone
+    fn actions(&mut self, s: &GameState) -> Option<Vec<UserAction>>;
+
+    /// Settles this tick's `actions` with anyone else playing. Called without the game state locked
+    fn sync(&mut self, actions: Vec<UserAction>) -> Option<Vec<UserAction>> {
+        Some(actions)
+    }
+}
+
+/// Whatever the frontend has sent since the last tick
+pub struct KeyboardController {
+    rx: Receiver<UserAction>,
+}
+impl KeyboardController {
+    pub fn new(rx: Receiver<UserAction>) -> KeyboardController {
+        KeyboardController { rx }
+    }
+}
+impl Controller for KeyboardController {
+    fn actions(&mut self, _s: &GameState) -> Option<Vec<UserAction>> {
+        let mut actions = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(action) => actions.push(action),
+                Err(TryRecvError::Empty) => return Some(actions),
+                Err(TryRecvError::Disconnected) => {
+                    println!("Tx closed. Exiting thread.");
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Plays a recording back, applying each action on the frame it was recorded on
+pub struct ReplayController {
+    actions: VecDeque<(usize, UserAction)>,
+}
+impl ReplayController {
+    pub fn new(replay: Replay) -> ReplayController {
+        ReplayController { actions: replay.actions.into() }
+    }
+}
+impl Controller for ReplayController {
+    fn actions(&mut self, s: &GameState) -> Option<Vec<UserAction>> {
+        let mut actions = Vec::new();
+        while let Some(&(frame_num, action)) = self.actions.front() {
+            if frame_num > s.frame_num {
+                break;
+            }
+            if frame_num < s.frame_num {
+                println!("Replay fell behind: action {:?} for frame {} applied on frame {}", action, frame_num, s.frame_num);
+            }
+            actions.push(action);
+            self.actions.pop_front();
+            if self.actions.is_empty() {
+                println!("Replay finished");
+            }
+        }
+        Some(actions)
+    }
+}
+
+/// Steers a player's snake with the same brain the rivals use
+pub struct BotController {
+    player: usize,
+    /// Kept apart from the game's RNG, so the bot's choices are recorded like anyone else's
+    rng: GameRng,
+}
+impl BotController {
+    pub fn new(player: usize, seed: u64) -> BotController {
+        let mut rng = GameRng::seed_from_u64(seed);
+        // Off the game's own stream, and the rivals'
+        rng.set_stream(2 + player as u64);
+        BotController { player, rng }
+    }
+}
+impl Controller for BotController {
+    fn actions(&mut self, s: &GameState) -> Option<Vec<UserAction>> {
+        let dir = s.players
+            .get(self.player)
+            .and_then(|p| choose_dir(&s.board, &p.snake, &mut self.rng));
+        Some(dir.map(|dir| UserAction::Turn(self.player, dir)).into_iter().collect())
+    }
+}
+
+/// Steers every rival. Replays don't need it, since they hold the rivals' turns
+pub struct RivalController {
+    rng: GameRng,
+}
+impl RivalController {
+    pub fn new(seed: u64) -> RivalController {
+        let mut rng = GameRng::seed_from_u64(seed);
+        // Off the game's own stream
+        rng.set_stream(1);
+        RivalController { rng }
+    }
+}
+impl Controller for RivalController {
+    fn actions(&mut self, s: &GameState) -> Option<Vec<UserAction>> {
+        if s.failed || s.final_score.is_some() {
+            return Some(Vec::new());
+        }
+        let actions = s.rivals
+            .iter()
+            .enumerate()
+            .filter_map(|(rival, r)| choose_dir(&s.board, &r.snake, &mut self.rng).map(|dir| UserAction::RivalTurn(rival, dir)))
+            .collect();
+        Some(actions)
+    }
+}
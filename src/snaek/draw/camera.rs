@@ -0,0 +1,66 @@
+use super::super::types::{B_HEIGHT, B_WIDTH};
+
+/// Pixels per cell at each zoom level. The overview fits the whole board instead
+const ZOOM_LEVELS: [f32; 5] = [0.0, 5.0, 10.0, 15.0, 20.0];
+const DEFAULT_ZOOM: usize = 2;
+/// How much of the way to its target the camera moves each frame
+const FOLLOW_RATE: f32 = 0.1;
+/// The camera cuts straight to a target this many cells away instead of scrolling the whole way
+const SNAP_DIST: f32 = 60.0;
+
+/// Follows the snakes around the board, easing toward them instead of jumping each time they move
+pub struct Camera {
+    zoom: usize,
+    /// The cell in the middle of the view, once there has been something to look at
+    center: Option<(f32, f32)>,
+}
+
+/// The part of the board that is on screen this frame
+pub struct View {
+    /// The cell at the top left corner of the view, which may be off the board when it is all on screen
+    pub left: f32,
+    pub top: f32,
+    /// The width in pixels of a single cell
+    pub cell_size: f32,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera { zoom: DEFAULT_ZOOM, center: None }
+    }
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom + 1).min(ZOOM_LEVELS.len() - 1);
+    }
+    pub fn zoom_out(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    /// Moves toward `target`, a position in cells, and works out what an area of `(w, h)` pixels shows
+    pub fn update(&mut self, target: (f32, f32), (w, h): (usize, usize)) -> View {
+        let fit = (w as f32 / B_WIDTH as f32).min(h as f32 / B_HEIGHT as f32);
+        let cell_size = ZOOM_LEVELS[self.zoom].max(fit);
+
+        let center = match self.center {
+            Some((x, y)) if (target.0 - x).abs() + (target.1 - y).abs() <= SNAP_DIST => {
+                (x + (target.0 - x) * FOLLOW_RATE, y + (target.1 - y) * FOLLOW_RATE)
+            }
+            _ => target,
+        };
+        self.center = Some(center);
+
+        View {
+            left: view_start(center.0, w as f32 / cell_size, B_WIDTH as f32),
+            top: view_start(center.1, h as f32 / cell_size, B_HEIGHT as f32),
+            cell_size,
+        }
+    }
+}
+
+/// Where a view of `view` cells around `center` starts, kept on the board
+fn view_start(center: f32, view: f32, board: f32) -> f32 {
+    if view >= board {
+        (board - view) / 2.0
+    } else {
+        (center - view / 2.0).clamp(0.0, board - view)
+    }
+}
@@ -12,7 +12,9 @@ use sdl2::{
 
 use super::{
     Frontend,
-    super::logic::UserAction
+    WindowAction,
+    super::logic::UserAction,
+    super::types::{Dir, PowerupType},
 };
 
 pub struct Sdl2Frontend {
@@ -22,7 +24,7 @@ pub struct Sdl2Frontend {
 impl Frontend for Sdl2Frontend {
     type Color = (u8, u8, u8);
     type Rect = (i32, i32, u32, u32);
-    type ActionIterator<'a> = std::iter::FilterMap<EventPollIterator<'a>, fn(Event) -> Option<UserAction>>;
+    type ActionIterator<'a> = std::iter::FilterMap<EventPollIterator<'a>, fn(Event) -> Option<WindowAction>>;
     
     fn new(size: (u32, u32)) -> Sdl2Frontend {
         let (canvas, sdl_context) = create_window(size);
@@ -52,9 +54,9 @@ impl Frontend for Sdl2Frontend {
             .poll_iter()
             .filter_map(|event| {
                 match event {
-                    Event::Quit{..} => Some(UserAction::Quit),
+                    Event::Quit{..} => Some(WindowAction::Game(UserAction::Quit)),
                     Event::KeyDown { keycode: Some(keycode), .. } => {
-                        key_to_user_action(keycode)
+                        key_to_window_action(keycode).or_else(|| key_to_user_action(keycode).map(WindowAction::Game))
                     }
                     _ => None
                 }
@@ -76,18 +78,48 @@ pub fn create_window((width, height): (u32, u32)) -> (WindowCanvas, Sdl) {
     (canvas, sdl_context)
 }
 
+/// Arrows, Enter and the keypad are player 1's; WASD, Space and the number row are player 2's
 fn key_to_user_action(keycode: Keycode) -> Option<UserAction> {
     match keycode {
-        Keycode::Up | Keycode::W => Some(UserAction::Up),
-        Keycode::Left | Keycode::A => Some(UserAction::Left),
-        Keycode::Down | Keycode::S => Some(UserAction::Down),
-        Keycode::Right | Keycode::D => Some(UserAction::Right),
+        Keycode::Up => Some(UserAction::Turn(0, Dir::Up)),
+        Keycode::Left => Some(UserAction::Turn(0, Dir::Left)),
+        Keycode::Down => Some(UserAction::Turn(0, Dir::Down)),
+        Keycode::Right => Some(UserAction::Turn(0, Dir::Right)),
+        Keycode::W => Some(UserAction::Turn(1, Dir::Up)),
+        Keycode::A => Some(UserAction::Turn(1, Dir::Left)),
+        Keycode::S => Some(UserAction::Turn(1, Dir::Down)),
+        Keycode::D => Some(UserAction::Turn(1, Dir::Right)),
         Keycode::F => Some(UserAction::Restart),
         Keycode::F3 => Some(UserAction::Debug),
+        Keycode::F5 => Some(UserAction::Save),
+        Keycode::F9 => Some(UserAction::Load),
         Keycode::Kp1 => Some(UserAction::ShopItem(0)),
         Keycode::Kp2 => Some(UserAction::ShopItem(1)),
         Keycode::Kp3 => Some(UserAction::ShopItem(2)),
-        Keycode::Space => Some(UserAction::Buy),
+        Keycode::Z => Some(UserAction::ShopItem(0)),
+        Keycode::X => Some(UserAction::ShopItem(1)),
+        Keycode::C => Some(UserAction::ShopItem(2)),
+        Keycode::Return | Keycode::KpEnter => Some(UserAction::Buy(0)),
+        Keycode::Space => Some(UserAction::Buy(1)),
+        Keycode::Kp4 => Some(UserAction::Activate(0, PowerupType::Water)),
+        Keycode::Kp5 => Some(UserAction::Activate(0, PowerupType::Explosive)),
+        Keycode::Kp6 => Some(UserAction::Activate(0, PowerupType::Shovel)),
+        Keycode::Kp7 => Some(UserAction::Activate(0, PowerupType::Seed)),
+        Keycode::Kp8 => Some(UserAction::Activate(0, PowerupType::Invincibility)),
+        Keycode::Num1 => Some(UserAction::Activate(1, PowerupType::Water)),
+        Keycode::Num2 => Some(UserAction::Activate(1, PowerupType::Explosive)),
+        Keycode::Num3 => Some(UserAction::Activate(1, PowerupType::Shovel)),
+        Keycode::Num4 => Some(UserAction::Activate(1, PowerupType::Seed)),
+        Keycode::Num5 => Some(UserAction::Activate(1, PowerupType::Invincibility)),
+        _ => None,
+    }
+}
+
+/// Keys that only change what this window shows
+fn key_to_window_action(keycode: Keycode) -> Option<WindowAction> {
+    match keycode {
+        Keycode::Equals | Keycode::Plus | Keycode::KpPlus => Some(WindowAction::ZoomIn),
+        Keycode::Minus | Keycode::KpMinus => Some(WindowAction::ZoomOut),
         _ => None,
     }
 }
\ No newline at end of file
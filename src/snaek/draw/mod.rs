@@ -19,11 +19,12 @@ use super::{
         CellFloor,
         CellObject,
         CellState,
-        Coord,
+        Dir,
         GameState,
         IndicatorType,
         PowerupType,
         SnakeColor,
+        SnakePart,
         B_HEIGHT,
         B_WIDTH,
         DRAW_MAX_USPT,
@@ -39,14 +40,16 @@ use super::{
 use crate::sized_color_space;
 
 pub mod draw_sdl2;
+pub mod camera;
 
 pub use draw_sdl2::Sdl2Frontend;
+use camera::Camera;
 use into_color::{as_color, color_space};
 
 pub trait Frontend {
     type Color: From<Color>;
     type Rect: From<Rect>;
-    type ActionIterator<'a>: Iterator<Item = UserAction> + 'a where Self: 'a;
+    type ActionIterator<'a>: Iterator<Item = WindowAction> + 'a where Self: 'a;
 
     fn new(size: (u32, u32)) -> Self;
     fn screen_size(&self) -> (u32, u32);
@@ -58,6 +61,14 @@ pub trait Frontend {
 }
 
 
+/// What the frontend picks up. Most of it is sent on to the game, but some only changes what this window shows
+#[derive(Clone, Copy, Debug)]
+pub enum WindowAction {
+    Game(UserAction),
+    ZoomIn,
+    ZoomOut,
+}
+
 ///////////////////////////////////////////////////////////
 
 pub fn window_loop<F: Frontend>(mut f: F, s: Arc<RwLock<GameState>>, tx: Sender<UserAction>) {
@@ -72,6 +83,11 @@ pub fn window_loop<F: Frontend>(mut f: F, s: Arc<RwLock<GameState>>, tx: Sender<
         let start = Instant::now();
 
         for action in f.get_actions() {
+            let action = match action {
+                WindowAction::Game(action) => action,
+                WindowAction::ZoomIn => { v.camera.zoom_in(); continue; }
+                WindowAction::ZoomOut => { v.camera.zoom_out(); continue; }
+            };
             match tx.send(action) {
                 Ok(_) => (),
                 Err(err) => {
@@ -109,9 +125,6 @@ pub fn window_loop<F: Frontend>(mut f: F, s: Arc<RwLock<GameState>>, tx: Sender<
 
 ///////////////////////////////////////////////////////////
 
-/// The width of a single cell in pixels
-const C_SIZE: usize = 10;
-
 pub fn draw_board<F: Frontend>(f: &mut F, s: &GameState, v: &mut ViewState) {
     f.set_color(EMPTY_COLOR.into());
     f.clear();
@@ -120,59 +133,53 @@ pub fn draw_board<F: Frontend>(f: &mut F, s: &GameState, v: &mut ViewState) {
     // The size in pixels of a cell in the scoreboard
     let sb_csize = h as usize / SB_HEIGHT;
     let sb_x = w as usize - sb_csize * SB_WIDTH;
+    // The second player's scoreboard goes on the left, pushing the board over
+    let board_x = if s.players.len() > 1 { sb_csize * SB_WIDTH } else { 0 };
 
-    // In blocks
-    let visible_w = sb_x / C_SIZE;
-    let visible_h = h as usize / C_SIZE;
     // In pixels
-    let Coord { x: snake_x, y: snake_y } = s.snake.head_pos();
-    let (w, h) = (B_WIDTH as isize, B_HEIGHT as isize);
-
-    let mut xstart = snake_x as isize - (visible_w / 2) as isize;
-    let mut xstop = xstart + visible_w as isize;
-    if xstop > w {
-        xstart -= xstop - w;
-        xstop = w;
-    }
-    if xstart < 0 {
-        xstart = 0;
-        xstop = w;
-    }
-    let (xstart, xstop) = (xstart as usize, xstop as usize);
-    let xrange = xstart..xstop;
-
-    let mut ystart = snake_y as isize - (visible_h / 2) as isize;
-    let mut ystop = ystart + visible_h as isize;
-    if ystop > h {
-        ystart -= ystop - h;
-        ystop = h;
-    }
-    if ystart < 0 {
-        ystart = 0;
-        ystop = h;
-    }
-    let (ystart, ystop) = (ystart as usize, ystop as usize);
-    let yrange = ystart..ystop;
-
-    // Start drawing at the top and left -- this also means we have room for the scoreboard
-    // Draw Board
-    for (y, row) in s.board[yrange].iter().enumerate() {
-        for (x, cell) in row[xrange.clone()].iter().enumerate() {
-            let rect = ((x * C_SIZE) as i32, (y * C_SIZE) as i32, C_SIZE as u32, C_SIZE as u32);
-            if let Some(color) = get_cell_color(*cell, s) {
+    let (visible_w, visible_h) = (sb_x - board_x, h as usize);
+    // Follow the point halfway between the players' heads
+    let num_players = s.players.len() as f32;
+    let snake_x = s.players.iter().map(|p| p.snake.head_pos().x as f32 + 0.5).sum::<f32>() / num_players;
+    let snake_y = s.players.iter().map(|p| p.snake.head_pos().y as f32 + 0.5).sum::<f32>() / num_players;
+    let view = v.camera.update((snake_x, snake_y), (visible_w, visible_h));
+
+    // In blocks
+    let xrange = view.left.max(0.0) as usize..((view.left + visible_w as f32 / view.cell_size).ceil() as usize).min(B_WIDTH);
+    let yrange = view.top.max(0.0) as usize..((view.top + visible_h as f32 / view.cell_size).ceil() as usize).min(B_HEIGHT);
+    // The pixel that a cell's left or top edge is drawn at. Rounding each edge rather than each
+    // size keeps neighboring cells from overlapping or leaving gaps at fractional zooms
+    let px = |x: usize| (board_x as f32 + (x as f32 - view.left) * view.cell_size).floor() as i32;
+    let py = |y: usize| ((y as f32 - view.top) * view.cell_size).floor() as i32;
+
+    // Draw the board first, so the scoreboards cover whatever cells hang over the edges
+    for y in yrange {
+        for x in xrange.clone() {
+            let rect = (px(x), py(y), (px(x + 1) - px(x)) as u32, (py(y + 1) - py(y)) as u32);
+            let cell = s.board[y][x];
+            if let CellObject::Snake(_, part) = cell.obj {
+                if view.cell_size >= MIN_SNAKE_SPRITE_SIZE {
+                    draw_snake_part(f, rect, cell, part, s);
+                    continue;
+                }
+            }
+            if let Some(color) = get_cell_color(cell, s) {
                 f.set_color(color.into());
                 f.draw_rect(rect.into());
             }
         }
     }
 
-    // Draw scoreboard
-    for (y, row) in s.scoreboard[..].iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            let rect = ((x * sb_csize + sb_x) as i32, (y * sb_csize) as i32, (sb_csize+1) as u32, (sb_csize+1) as u32);
-            if let Some(color) = get_cell_color(*cell, s) {
-                f.set_color(color.into());
-                f.draw_rect(rect.into());
+    // Draw scoreboards
+    for (i, player) in s.players.iter().enumerate() {
+        let player_sb_x = if i == 0 { sb_x } else { 0 };
+        for (y, row) in player.scoreboard[..].iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let rect = ((x * sb_csize + player_sb_x) as i32, (y * sb_csize) as i32, (sb_csize+1) as u32, (sb_csize+1) as u32);
+                if let Some(color) = get_cell_color(*cell, s) {
+                    f.set_color(color.into());
+                    f.draw_rect(rect.into());
+                }
             }
         }
     }
@@ -231,7 +238,7 @@ pub fn draw_board<F: Frontend>(f: &mut F, s: &GameState, v: &mut ViewState) {
         let ds_csize = 3;
         // let ds_x = (visible_w * C_SIZE).saturating_sub(100 * ds_csize);
         let ds_x = sb_x;
-        let ds_y = (visible_h * C_SIZE).saturating_sub(100 * ds_csize);
+        let ds_y = visible_h.saturating_sub(100 * ds_csize);
         for (y, row) in v.debug_screen[..].iter().enumerate() {
             for (x, cell) in row.iter().enumerate() {
                 let rect = ((x * ds_csize + ds_x) as i32, (y * ds_csize + ds_y) as i32, (ds_csize+1) as u32, (ds_csize+1) as u32);
@@ -244,6 +251,27 @@ pub fn draw_board<F: Frontend>(f: &mut F, s: &GameState, v: &mut ViewState) {
     }
 }
 
+/// Draws a piece of snake joined on to the pieces next to it
+fn draw_snake_part<F: Frontend>(f: &mut F, (x, y, w, h): Rect, cell: CellState, part: SnakePart, s: &GameState) {
+    if let Some(color) = get_floor_color(cell.floor, cell.elev, cell.fertility) {
+        f.set_color(color.into());
+        f.draw_rect((x, y, w, h).into());
+    }
+    let Some(color) = get_object_color(cell.obj, s) else { return };
+    f.set_color(color.into());
+    let (mx, my) = (w / 5, h / 5);
+    f.draw_rect((x + mx as i32, y + my as i32, w - 2 * mx, h - 2 * my).into());
+    for dir in part.front.into_iter().chain(part.back) {
+        let arm = match dir {
+            Dir::Up => (x + mx as i32, y, w - 2 * mx, my),
+            Dir::Down => (x + mx as i32, y + (h - my) as i32, w - 2 * mx, my),
+            Dir::Left => (x, y + my as i32, mx, h - 2 * my),
+            Dir::Right => (x + (w - mx) as i32, y + my as i32, mx, h - 2 * my),
+        };
+        f.draw_rect(arm.into());
+    }
+}
+
 fn get_cell_color(cell: CellState, s: &GameState) -> Option<Color> {
     if cell.obj == CellObject::None {
         get_floor_color(cell.floor, cell.elev, cell.fertility)
@@ -253,17 +281,19 @@ fn get_cell_color(cell: CellState, s: &GameState) -> Option<Color> {
 }
 
 fn get_floor_color(floor: CellFloor, elev: u8, fertility: i8) -> Option<Color> {
-    let fertility = if fertility < 0 { 0 } else { fertility as usize };
+    let fertility = fertility.clamp(0, MAX_FERTILITY) as usize;
     match floor {
         CellFloor::Empty => Some(TERRAIN_COLORS[elev as usize][fertility]),
-        CellFloor::Water { depth } => Some(WATER_COLORS[depth as usize]),
-        CellFloor::Lava { depth } => Some(LAVA_COLORS[depth as usize]),
+        CellFloor::Water { depth, .. } => Some(WATER_COLORS[depth as usize]),
+        CellFloor::Lava { depth, .. } => Some(LAVA_COLORS[depth as usize]),
         CellFloor::Seed { height, saturation } => Some(SEED_COLORS[elev as usize][fertility][height as usize]),
+        CellFloor::Fire { fuel } => Some(FIRE_COLORS[fuel as usize]),
         CellFloor::Indicator(IndicatorType::Empty) => None,
         CellFloor::Indicator(IndicatorType::MSPTNormal) => Some(MSPT_NORMAL_COLOR),
         CellFloor::Indicator(IndicatorType::MSPTOver) => Some(MSPT_OVER_COLOR),
         CellFloor::Indicator(IndicatorType::Coin) => Some(COIN_COLOR),
         CellFloor::Indicator(IndicatorType::PM) => Some(PM_COLOR),
+        CellFloor::Indicator(IndicatorType::Text) => Some(TEXT_COLOR),
         
         CellFloor::Indicator(IndicatorType::Powerup(PowerupType::Water)) => Some(WATER_COLOR),
         CellFloor::Indicator(IndicatorType::Powerup(PowerupType::Explosive)) => Some(EXPLOSIVE_COLOR),
@@ -283,18 +313,29 @@ fn get_object_color(obj: CellObject, s: &GameState) -> Option<Color> {
         CellObject::Wall => Some(WALL_COLOR),
         CellObject::Snake(SnakeColor::DarkRed, _) => Some(SNAKE_COLOR_DARK_RED),
         CellObject::Snake(SnakeColor::LightRed, _) => Some(SNAKE_COLOR_LIGHT_RED),
-        CellObject::Snake(SnakeColor::Head, _) => Some(if s.invinc_time != 0 { SNAKE_COLOR_HEAD_WITH_INVINC } else { SNAKE_COLOR_HEAD }),
+        CellObject::Snake(SnakeColor::Head, _) => Some(if is_invincible(s, 0) { SNAKE_COLOR_HEAD_WITH_INVINC } else { SNAKE_COLOR_HEAD }),
+        CellObject::Snake(SnakeColor::DarkBlue, _) => Some(SNAKE_COLOR_DARK_BLUE),
+        CellObject::Snake(SnakeColor::LightBlue, _) => Some(SNAKE_COLOR_LIGHT_BLUE),
+        CellObject::Snake(SnakeColor::BlueHead, _) => Some(if is_invincible(s, 1) { SNAKE_COLOR_HEAD_WITH_INVINC } else { SNAKE_COLOR_BLUE_HEAD }),
+        CellObject::Snake(SnakeColor::RivalDark, _) => Some(SNAKE_COLOR_RIVAL_DARK),
+        CellObject::Snake(SnakeColor::RivalLight, _) => Some(SNAKE_COLOR_RIVAL_LIGHT),
+        CellObject::Snake(SnakeColor::RivalHead, _) => Some(SNAKE_COLOR_RIVAL_HEAD),
         CellObject::Food(..) => Some(FOOD_COLOR),
         CellObject::Border => Some(BORDER_COLOR),
     }
 }
 
+fn is_invincible(s: &GameState, player: usize) -> bool {
+    s.players.get(player).map_or(false, |p| p.invinc_time != 0)
+}
+
 pub const DS_WIDTH: usize = 100;
 pub const DS_HEIGHT: usize = 100;
 
 pub struct ViewState {
     pub debug_screen: Board<DS_WIDTH, DS_HEIGHT>,
     pub debug_info: ViewDebugInfo,
+    pub camera: Camera,
 }
 
 #[derive(Default)]
@@ -307,10 +348,14 @@ fn reset_view_state() -> ViewState {
     ViewState {
         debug_screen: Board::<DS_WIDTH, DS_HEIGHT>::new_filled(CellFloor::Indicator(IndicatorType::Empty)),
         debug_info: ViewDebugInfo::default(),
+        camera: Camera::new(),
     }
 }
 
 type Rect = (i32, i32, u32, u32);
+
+/// Below this many pixels a cell, snakes are drawn as plain cells
+const MIN_SNAKE_SPRITE_SIZE: f32 = 5.0;
 type Color = (u8, u8, u8);
 
 // Floor colors
@@ -326,6 +371,12 @@ const BORDER_COLOR: Color = as_color!("#42005e");
 const SNAKE_COLOR_LIGHT_RED: Color = as_color!("#ff6038");
 const SNAKE_COLOR_DARK_RED: Color = as_color!("#871d03");
 const SNAKE_COLOR_HEAD: Color = as_color!("#eb9b2d");
+const SNAKE_COLOR_LIGHT_BLUE: Color = as_color!("#38a0ff");
+const SNAKE_COLOR_DARK_BLUE: Color = as_color!("#03368a");
+const SNAKE_COLOR_BLUE_HEAD: Color = as_color!("#2dd5eb");
+const SNAKE_COLOR_RIVAL_LIGHT: Color = as_color!("#8c8c8c");
+const SNAKE_COLOR_RIVAL_DARK: Color = as_color!("#3d3d3d");
+const SNAKE_COLOR_RIVAL_HEAD: Color = as_color!("#c2c2c2");
 const SNAKE_COLOR_HEAD_WITH_INVINC: Color = INVINC_COLOR;
 // const SEED_COLORS: [[Color; 256]; MAX_WATER_DIST as usize + 1] = [
 //     seed_colors::SEED_HEIGHT_COLORS_0,
@@ -511,6 +562,7 @@ const SHOVEL_COLOR: Color = as_color!("#422417");
 // Other colors
 const COIN_COLOR: Color = as_color!("#bdb600");
 const PM_COLOR: Color = as_color!("#62fa4b");
+const TEXT_COLOR: Color = as_color!("#1a1a1a");
 
 // sized_color_space!{
 //     TERRAIN_COLORS = [
@@ -556,6 +608,16 @@ sized_color_space!{
     NUM_WATER_COLORS = 256
 }
 
+sized_color_space!{
+    FIRE_COLORS = [
+        ("#5c1a00", 0.0),
+        ("#e33b00", 0.05),
+        ("#ff9d00", 0.12),
+        ("#fff04f", 1.0)
+    ],
+    NUM_FIRE_COLORS = 256
+}
+
 sized_color_space!{
     LAVA_COLORS = [
         ("#ffb054", 0.0),
@@ -0,0 +1,109 @@
+use scoped_threadpool::Pool;
+
+use super::{
+    controller::{BotController, Controller, RivalController},
+    levels::LevelState,
+    logic::{
+        advance_board,
+        handle_actions,
+        reset,
+        reset_with_seed,
+        NUM_BOARD_ADVANCE_THREADS,
+    },
+    types::{
+        CellFloor,
+        GameState,
+    },
+};
+
+/// Runs up to `ticks` logic frames without a window and prints a summary. With `bot`, a bot steers
+pub fn run_headless(ticks: usize, seed: Option<u64>, level_index: usize, bot: bool) -> GameState {
+    // Poll the Lazy
+    crate::text::GRIDS.len();
+
+    let (s, l) = match seed {
+        Some(seed) => reset_with_seed(seed, level_index, 1),
+        None => reset(level_index, 1),
+    };
+    let s = play(s, l, ticks, bot);
+
+    print_summary(&s);
+    s
+}
+
+/// Plays `ticks` logic frames of a game that has already been set up
+fn play(mut s: GameState, mut l: Box<dyn LevelState>, ticks: usize, bot: bool) -> GameState {
+    let mut pool = Pool::new(NUM_BOARD_ADVANCE_THREADS);
+    let mut controllers: Vec<Box<dyn Controller>> = Vec::new();
+    if bot {
+        controllers.push(Box::new(BotController::new(0, s.seed)));
+    }
+    controllers.push(Box::new(RivalController::new(s.seed)));
+    let mut turns = Default::default();
+
+    for _ in 0..ticks {
+        if s.failed || s.final_score.is_some() {
+            break;
+        }
+        handle_actions(&mut controllers, &mut s, &mut l, &mut turns, &mut None);
+        advance_board(&mut s, &mut *l, &mut pool);
+    }
+    s
+}
+
+fn print_summary(s: &GameState) {
+    let (mut water, mut lava, mut seed, mut fire) = (0usize, 0usize, 0usize, 0usize);
+    for cell in s.board.cells() {
+        match cell.floor {
+            CellFloor::Water { .. } => water += 1,
+            CellFloor::Lava { .. } => lava += 1,
+            CellFloor::Seed { .. } => seed += 1,
+            CellFloor::Fire { .. } => fire += 1,
+            _ => {}
+        }
+    }
+
+    println!("=== Headless summary ===");
+    println!("Seed:         {}", s.seed);
+    println!("Level:        {}", s.level.name);
+    println!("Frames:       {}", s.frame_num);
+    for (i, player) in s.players.iter().enumerate() {
+        println!("Player {}", i + 1);
+        println!("  Coins:        {}", player.coins);
+        println!("  Snake length: {}", player.snake.len());
+    }
+    println!("Rivals alive: {}", s.rivals.len());
+    for rival in &s.rivals {
+        println!("  Length {}, {} coins", rival.snake.len(), rival.coins);
+    }
+    println!("Water cells:  {}", water);
+    println!("Lava cells:   {}", lava);
+    println!("Seed cells:   {}", seed);
+    println!("Fire cells:   {}", fire);
+    match s.final_score {
+        Some(score) => println!("Final score:  {}", score),
+        None => println!("Final score:  level not complete"),
+    }
+    match s.fail_reason {
+        Some(reason) => println!("Failed:       {}", reason.message()),
+        None => println!("Failed:       no"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{test_util::VOLCANO, types::Coord};
+
+    #[test]
+    fn bot_plays_a_level_through() {
+        let (mut s, l) = reset_with_seed(7, VOLCANO, 1);
+        // Start near the end of the level, so it doesn't take long to finish
+        s.level_time = s.level.time_limit - 60;
+        let s = play(s, l, 100, true);
+        assert_eq!(s.frame_num, 60);
+        assert_eq!(s.final_score, Some(550));
+        // Left alone, the snake would have gone straight along the top to (65, 5)
+        assert_eq!(s.players[0].snake.head_pos(), Coord { x: 8, y: 48 });
+    }
+}
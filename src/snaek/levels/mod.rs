@@ -1,20 +1,27 @@
+use std::io;
+
 use rand::Rng;
 
+use super::bytes::{ByteReader, ByteWriter};
 use super::types::{
     GameState,
+    GameRng,
     PowerupType,
     ShopState,
-    ShopItem, proc_array
+    ShopItem,
+    NUM_POWERUP_TYPES,
+    NUM_SHOP_ITEMS,
+    proc_array
 };
 
-mod lakes;
-use lakes::LAKES_LEVEL;
-
 mod volcano;
 use volcano::VOLCANO_LEVEL;
 
+mod tutorial;
+use tutorial::TUTORIAL_LEVEL;
+
 pub static LEVELS: &[&Level] = &[
-    // &LAKES_LEVEL,
+    &TUTORIAL_LEVEL,
     &VOLCANO_LEVEL,
 ];
 
@@ -22,38 +29,109 @@ pub struct Level {
     pub name: &'static str,
     pub raw_board: &'static [u8],
     pub index: usize,
-    pub new_level_state: fn () -> Box<dyn LevelState>,
+    /// The number of logic ticks before the level ends
+    pub time_limit: usize,
+    /// Each logic tick, the price multiplier goes up with a chance of one in this many
+    pub inflation_one_in: u32,
+    /// How many rival snakes start the level
+    pub num_rivals: usize,
+    pub new_level_state: fn (&mut GameRng) -> Box<dyn LevelState>,
 }
 
 pub trait LevelState: Send {
     fn update(&mut self, s: &mut GameState);
     fn reset_shop(&mut self, s: &mut GameState);
-    fn new_shop(&mut self) -> ShopState;
+    fn new_shop(&mut self, rng: &mut GameRng) -> ShopState;
+    /// Lets a level end early once its objective is met. The time limit applies either way
+    fn is_complete(&self, _s: &GameState) -> bool {
+        false
+    }
+    /// Writes everything `load` needs to restore this state
+    fn save(&self, w: &mut ByteWriter);
+    fn load(&mut self, r: &mut ByteReader) -> io::Result<()>;
 }
 
-pub fn new_shop_rand<F: FnMut(PowerupType) -> usize>(price_multiplier: usize, mut price: F) -> ShopState {
-    ShopState {
-        powerups: proc_array(|_| {
-            let kind = rand::random();
-            let price = price(kind);
-            ShopItem { kind, price }
-        }),
-        selected: 0,
-        price_multiplier,
+/// What a level's shop offers: the `fixed` offerings in order, then picks from `pool`
+pub struct ShopScript {
+    /// The base price of each powerup, indexed by `PowerupType`
+    pub prices: [usize; NUM_POWERUP_TYPES],
+    pub fixed: &'static [[PowerupType; NUM_SHOP_ITEMS]],
+    /// Each powerup that can be drawn and its weight. Should hold at least `NUM_SHOP_ITEMS` powerups
+    pub pool: &'static [(PowerupType, u32)],
+    pub start_price_multiplier: usize,
+}
+impl ShopScript {
+    /// The powerups the shop offers the `load`th time it loads, counting from 0
+    fn offering(&self, load: usize, rng: &mut impl Rng) -> [ShopItem; NUM_SHOP_ITEMS] {
+        let kinds = match self.fixed.get(load) {
+            Some(&kinds) => kinds,
+            None => self.draw(rng),
+        };
+        kinds.map(|kind| ShopItem { kind, price: self.prices[kind as usize] })
+    }
+    /// Draws from the pool without replacement. If the pool runs dry, it is refilled
+    fn draw(&self, rng: &mut impl Rng) -> [PowerupType; NUM_SHOP_ITEMS] {
+        let mut left = Vec::new();
+        proc_array(|_| {
+            if left.is_empty() {
+                left.extend_from_slice(self.pool);
+            }
+            let total: u32 = left.iter().map(|&(_, weight)| weight).sum();
+            let mut choice = rng.gen_range(0..total.max(1));
+            let i = left.iter()
+                .position(|&(_, weight)| {
+                    if choice < weight {
+                        return true;
+                    }
+                    choice -= weight;
+                    false
+                })
+                .unwrap_or(0);
+            left.swap_remove(i).0
+        })
     }
 }
 
-pub fn reset_shop_rand<F: FnMut(PowerupType) -> usize>(shop: &mut ShopState, mut price: F) {
-    for item in &mut shop.powerups {
-        let kind = rand::random();
-        let price = price(kind);
-        *item = ShopItem {
-            kind,
-            price,
+/// Runs a level's `ShopScript`. Level states hold one and forward their shop methods to it.
+pub struct ScriptedShop {
+    script: &'static ShopScript,
+    loads: usize,
+}
+impl ScriptedShop {
+    pub fn new(script: &'static ShopScript) -> ScriptedShop {
+        ScriptedShop { script, loads: 0 }
+    }
+    pub fn reset_shop(&mut self, s: &mut GameState) {
+        s.shop.powerups = self.script.offering(self.loads, &mut s.rng);
+        self.loads += 1;
+    }
+    pub fn new_shop(&mut self, rng: &mut GameRng) -> ShopState {
+        let powerups = self.script.offering(self.loads, rng);
+        self.loads += 1;
+        ShopState {
+            powerups,
+            selected: 0,
+            price_multiplier: self.script.start_price_multiplier,
         }
     }
+    pub fn save(&self, w: &mut ByteWriter) {
+        w.usize(self.loads);
+    }
+    pub fn load(&mut self, r: &mut ByteReader) -> io::Result<()> {
+        self.loads = r.usize()?;
+        Ok(())
+    }
 }
 
+/// Every powerup, equally likely
+pub const EVEN_POOL: &[(PowerupType, u32)] = &[
+    (PowerupType::Water, 1),
+    (PowerupType::Explosive, 1),
+    (PowerupType::Shovel, 1),
+    (PowerupType::Seed, 1),
+    (PowerupType::Invincibility, 1),
+];
+
 // pub static _HI_LEVEL: &[u8] = include_bytes!("../../res/levels/hi.bin");
 // pub static _RIVER_LEVEL: &[u8] = include_bytes!("../../res/levels/river.bin");
 // pub static LONELY_WORLD_LEVEL: &[u8] = include_bytes!("../../res/levels/lonely_world.bin");
@@ -0,0 +1,205 @@
+use std::io;
+
+use crate::snaek::{
+    bytes::{ByteReader, ByteWriter, invalid_data},
+    types::{
+        GameState,
+        GameRng,
+        ShopState,
+        PowerupType,
+        CellFloor,
+        CellState,
+        Coord,
+        Dir,
+        IndicatorType,
+        B_WIDTH,
+        B_HEIGHT,
+        MATURE_SEED_HEIGHT,
+        NUM_POWERUP_TYPES,
+        NUM_SHOP_ITEMS,
+    },
+    art::{
+        PlusLava,
+        BoardArt,
+    }
+};
+use crate::text::C_HEIGHT;
+
+use super::{
+    Level,
+    LevelState,
+    ScriptedShop,
+    ShopScript,
+    EVEN_POOL,
+};
+
+
+pub static TUTORIAL_LEVEL: Level = Level {
+    name: "Tutorial",
+    raw_board: &TUTORIAL_BOARD,
+    index: 0,
+    time_limit: 30000,
+    inflation_one_in: 2000,
+    num_rivals: 0,
+    new_level_state: |_| Box::new(TutorialState::new()),
+};
+
+static TUTORIAL_SHOP: ShopScript = ShopScript {
+    prices: [10; NUM_POWERUP_TYPES],
+    fixed: &[
+        [PowerupType::Water; NUM_SHOP_ITEMS],
+        [PowerupType::Seed; NUM_SHOP_ITEMS],
+    ],
+    pool: EVEN_POOL,
+    start_price_multiplier: 10,
+};
+
+/// How long the snake has to stay away from the lava to finish the tutorial
+const LAVA_STEP_TICKS: usize = 300;
+/// Where the lava comes out once the lava step starts. The land slopes away from the start toward it
+const LAVA_SOURCE: (usize, usize) = (120, 70);
+const TEXT_LINE_H: usize = C_HEIGHT + 2;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Steer,
+    Water,
+    Seed,
+    Grow,
+    Lava,
+    Done,
+}
+impl Step {
+    const ALL: [Step; 6] = [Step::Steer, Step::Water, Step::Seed, Step::Grow, Step::Lava, Step::Done];
+
+    fn instructions(self) -> &'static [&'static str] {
+        match self {
+            Step::Steer => &["arrows or wasd", "to steer"],
+            Step::Water => &["enter or space buys", "water. keypad 4 or 1", "pours it"],
+            Step::Seed => &["buy seeds, then", "keypad 7 or 4 plants", "them near water"],
+            Step::Grow => &["wait for the plants", "to grow. they make", "coins and food"],
+            Step::Lava => &["lava is coming.", "stay away from it"],
+            Step::Done => &["well done.", "press f to go on"],
+        }
+    }
+}
+
+struct TutorialState {
+    step: Step,
+    /// Where the current step's instructions are drawn, once they have been
+    text_pos: Option<Coord>,
+    lava_time: usize,
+    shop: ScriptedShop,
+}
+impl TutorialState {
+    fn new() -> TutorialState {
+        TutorialState {
+            step: Step::Steer,
+            text_pos: None,
+            lava_time: 0,
+            shop: ScriptedShop::new(&TUTORIAL_SHOP),
+        }
+    }
+
+    fn is_step_done(&self, s: &GameState) -> bool {
+        match self.step {
+            Step::Steer => s.players.iter().any(|p| p.snake.dir() != Dir::Right),
+            Step::Water => s.board.cells().any(|cell| matches!(cell.floor, CellFloor::Water { .. })),
+            Step::Seed => s.seed_count > 0,
+            Step::Grow => s.board.cells().any(|cell| matches!(cell.floor, CellFloor::Seed { height, .. } if height >= MATURE_SEED_HEIGHT)),
+            Step::Lava => self.lava_time >= LAVA_STEP_TICKS,
+            Step::Done => false,
+        }
+    }
+
+    /// Writes the instructions on bare ground just below the first player's head
+    fn show_instructions(&mut self, s: &mut GameState) {
+        let write: fn(&mut CellState) = |cell| {
+            if cell.floor == CellFloor::Empty {
+                cell.floor = CellFloor::Indicator(IndicatorType::Text);
+            }
+        };
+        let head = s.players[0].snake.head_pos();
+        let pos = Coord {
+            x: head.x.min(B_WIDTH - 80),
+            y: (head.y + 4).min(B_HEIGHT - 4 * TEXT_LINE_H),
+        };
+        for (i, line) in self.step.instructions().iter().enumerate() {
+            s.board.text(line, (pos.x, pos.y + i * TEXT_LINE_H), write, ());
+        }
+        self.text_pos = Some(pos);
+    }
+
+    /// Erases whatever is left of the instructions. Text that has been flooded or grown over stays that way
+    fn hide_instructions(&mut self, s: &mut GameState) {
+        let clear: fn(&mut CellState) = |cell| {
+            if cell.floor == CellFloor::Indicator(IndicatorType::Text) {
+                cell.floor = CellFloor::Empty;
+            }
+        };
+        if let Some(pos) = self.text_pos.take() {
+            for (i, line) in self.step.instructions().iter().enumerate() {
+                s.board.text(line, (pos.x, pos.y + i * TEXT_LINE_H), clear, ());
+            }
+        }
+    }
+}
+impl LevelState for TutorialState {
+    fn update(&mut self, s: &mut GameState) {
+        if self.text_pos.is_none() {
+            self.show_instructions(s);
+        }
+        if self.step == Step::Lava {
+            s.board.pt(LAVA_SOURCE, PlusLava(1));
+            self.lava_time += 1;
+        }
+        if self.is_step_done(s) {
+            self.hide_instructions(s);
+            self.step = Step::ALL[self.step as usize + 1];
+            self.show_instructions(s);
+        }
+    }
+    fn reset_shop(&mut self, s: &mut GameState) {
+        self.shop.reset_shop(s);
+    }
+    fn new_shop(&mut self, rng: &mut GameRng) -> ShopState {
+        self.shop.new_shop(rng)
+    }
+    fn is_complete(&self, _s: &GameState) -> bool {
+        self.step == Step::Done
+    }
+    fn save(&self, w: &mut ByteWriter) {
+        self.shop.save(w);
+        w.u8(self.step as u8);
+        w.bool(self.text_pos.is_some());
+        let Coord { x, y } = self.text_pos.unwrap_or(Coord { x: 0, y: 0 });
+        w.usize(x);
+        w.usize(y);
+        w.usize(self.lava_time);
+    }
+    fn load(&mut self, r: &mut ByteReader) -> io::Result<()> {
+        self.shop.load(r)?;
+        self.step = *Step::ALL.get(r.u8()? as usize).ok_or_else(|| invalid_data("Unknown tutorial step"))?;
+        let shown = r.bool()?;
+        let pos = Coord { x: r.usize()?, y: r.usize()? };
+        self.text_pos = Some(pos).filter(|_| shown);
+        self.lava_time = r.usize()?;
+        Ok(())
+    }
+}
+
+/// Flat, fertile land sloping down from the top left corner
+static TUTORIAL_BOARD: [u8; B_WIDTH * B_HEIGHT * 3] = tutorial_board();
+
+const fn tutorial_board() -> [u8; B_WIDTH * B_HEIGHT * 3] {
+    let mut bytes = [0; B_WIDTH * B_HEIGHT * 3];
+    let mut i = 0;
+    while i < B_WIDTH * B_HEIGHT {
+        let (x, y) = (i % B_WIDTH, i / B_WIDTH);
+        // Floor stays empty
+        bytes[i * 3 + 1] = (40 + (B_WIDTH - x) / 8 + (B_HEIGHT - y) / 8) as u8;
+        bytes[i * 3 + 2] = 10;
+        i += 1;
+    }
+    bytes
+}
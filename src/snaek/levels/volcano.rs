@@ -1,9 +1,13 @@
+use std::io;
+
 use crate::snaek::{
+    bytes::{ByteReader, ByteWriter},
     types::{
         GameState,
+        GameRng,
         ShopState,
-        ShopItem,
         PowerupType,
+        NUM_POWERUP_TYPES,
         NUM_SHOP_ITEMS,
     },
     art::{
@@ -15,25 +19,40 @@ use crate::snaek::{
 use super::{
     Level,
     LevelState,
-    reset_shop_rand,
-    new_shop_rand
+    ScriptedShop,
+    ShopScript,
+    EVEN_POOL,
 };
 
 
 pub static VOLCANO_LEVEL: Level = Level {
     name: "Volcano",
     raw_board: VOLCANO_BOARD,
-    index: 0,
-    new_level_state: || Box::new(VolcanoState::new()),
+    index: 1,
+    time_limit: 3000,
+    inflation_one_in: 500,
+    num_rivals: 2,
+    new_level_state: |_| Box::new(VolcanoState::new()),
+};
+
+/// Water then seeds so the first forest can be planted, then anything
+static VOLCANO_SHOP: ShopScript = ShopScript {
+    prices: [10; NUM_POWERUP_TYPES],
+    fixed: &[
+        [PowerupType::Water; NUM_SHOP_ITEMS],
+        [PowerupType::Seed; NUM_SHOP_ITEMS],
+    ],
+    pool: EVEN_POOL,
+    start_price_multiplier: 10,
 };
 
 struct VolcanoState {
-    shop_reset_count: usize,
+    shop: ScriptedShop,
 }
 impl VolcanoState {
     fn new() -> VolcanoState {
         VolcanoState {
-            shop_reset_count: 0
+            shop: ScriptedShop::new(&VOLCANO_SHOP),
         }
     }
 }
@@ -42,15 +61,16 @@ impl LevelState for VolcanoState {
         s.board.pt((95, 75), PlusLava(1));
     }
     fn reset_shop(&mut self, s: &mut GameState) {
-        match self.shop_reset_count {
-            0 => s.shop.powerups = [ShopItem { kind: PowerupType::Water, price: 10 }; NUM_SHOP_ITEMS],
-            1 => s.shop.powerups = [ShopItem { kind: PowerupType::Seed, price: 10 }; NUM_SHOP_ITEMS],
-            _ => reset_shop_rand(&mut s.shop, |_| 10),
-        }
-        self.shop_reset_count += 1;
+        self.shop.reset_shop(s);
+    }
+    fn new_shop(&mut self, rng: &mut GameRng) -> ShopState {
+        self.shop.new_shop(rng)
+    }
+    fn save(&self, w: &mut ByteWriter) {
+        self.shop.save(w);
     }
-    fn new_shop(&mut self) -> ShopState {
-        new_shop_rand(10, |_| 10)
+    fn load(&mut self, r: &mut ByteReader) -> io::Result<()> {
+        self.shop.load(r)
     }
 }
 
@@ -1,10 +1,9 @@
 
 use std::{
-    sync::{Arc, RwLock, mpsc::{Receiver, TryRecvError}},
+    sync::{Arc, RwLock},
+    collections::VecDeque,
     thread,
     time::{Duration, Instant},
-    hash::{Hash, Hasher},
-    collections::hash_map::DefaultHasher,
 };
 use scoped_threadpool::Pool;
 
@@ -25,26 +24,44 @@ use super::{
         Coord,
         DebugInfo,
         Dir,
+        FailReason,
+        GameRng,
         GameState,
+        Player,
         ShopItem,
         Snake,
         SnakeColor,
+        MAX_PLAYERS,
+        PLAYER_SNAKE_COLORS,
+        RIVAL_SNAKE_COLORS,
+        START_SNAKE_LEN,
         B_HEIGHT,
         B_WIDTH,
+        LAVA_MAX_HEAT,
         LOGIC_MAX_MSPT,
+        MATURE_SEED_HEIGHT,
         MIN_SATURATION_FOR_SEED_SPREAD,
         NUM_SHOP_ITEMS,
+        NUM_POWERUP_TYPES,
+        PowerupType,
     },
     levels::{
         LEVELS,
         LevelState,
     },
+    controller::Controller,
+    path::is_lethal,
+    replay::ReplayRecorder,
+    rival::spawn_rivals,
+    save::{self, quicksave_path},
     scoreboard::{
         SCORE_BANNER_VERT,
         ScoreboardArt,
         ShopItemFill
     },
     art::{
+        disk,
+        MinusElev,
         PlusWater,
         PlusSeed,
     },
@@ -53,65 +70,95 @@ use super::{
 use super::art::BoardArt;
 
 pub const INVINC_TIME: usize = 100;
+/// How many coins each unit of snake length is worth at the end of a level
+pub const COINS_PER_LENGTH: usize = 50;
+/// The radius around the head the shovel digs from and drops onto
+pub const SHOVEL_RADIUS: usize = 1;
+/// How much elevation one shovel digs up
+pub const SHOVEL_DIRT: u8 = 1;
 
-pub fn reset() -> (GameState, Box<dyn LevelState>) {
-    let current_level_index = 0;
-    let level = &LEVELS[current_level_index];
-    let mut l = (level.new_level_state)();
-    let shop = l.new_shop();
-    println!("Level 1: {}", level.name);
+pub fn reset(level_index: usize, num_players: usize) -> (GameState, Box<dyn LevelState>) {
+    reset_with_seed(rand::thread_rng().gen(), level_index, num_players)
+}
+
+/// Builds a new game whose every random decision is derived from `seed`
+pub fn reset_with_seed(seed: u64, level_index: usize, num_players: usize) -> (GameState, Box<dyn LevelState>) {
+    let level = LEVELS[level_index];
+    let mut rng = GameRng::seed_from_u64(seed);
+    let mut l = (level.new_level_state)(&mut rng);
+    let shop = l.new_shop(&mut rng);
+    let price_multiplier = shop.price_multiplier;
+    println!("Seed: {}", seed);
+    println!("Level {}: {}", level.index + 1, level.name);
     let mut board = Board::from_bytes(level.raw_board);
-    let scoreboard = Board::from_bytes(SCORE_BANNER_VERT);
-    let snake = Snake::new((5, 5), Dir::Right, 5);
+    let players = (0..num_players.clamp(1, MAX_PLAYERS))
+        .map(|i| Player {
+            snake: Snake::new(Player::start(i), Dir::Right, START_SNAKE_LEN),
+            scoreboard: Board::from_bytes(SCORE_BANNER_VERT),
+            coins: 300,
+            coin_progress: 0,
+            invinc_time: 0,
+            dirt: 0,
+            inventory: [0; NUM_POWERUP_TYPES],
+        })
+        .collect();
     
     // _place_debug(&mut board);
     
     let mut s = GameState {
         level,
-        scoreboard,
         shop,
         board,
-        snake,
-        coins: 300,
-        invinc_time: 0,
+        players,
+        rivals: Vec::new(),
+        price_multiplier,
         failed: false,
+        fail_reason: None,
+        seed_count: 0,
+        level_time: 0,
+        final_score: None,
         frame_num: 0,
         debug_screen: false,
         debug_info: DebugInfo::default(),
-        salt: rand::thread_rng().gen(),
+        seed,
+        salt: rng.gen(),
+        rng,
     };
-    l.reset_shop(&mut s);
-    s.scoreboard.shop(&s.shop);
-    s.scoreboard.pm(&s.shop);
-    s.scoreboard.coins(s.coins);
+    spawn_rivals(&mut s);
+    s.redraw_scoreboards();
 
     (s, l)
 }
 
-const NUM_BOARD_ADVANCE_THREADS: u32 = 4;
+pub const NUM_BOARD_ADVANCE_THREADS: u32 = 4;
+/// How many turns can wait to be applied. Any more pressed in the meantime are dropped
+const MAX_QUEUED_TURNS: usize = 3;
 
 ///////////////////////////////////////////////////////////
-pub fn spawn_logic_thread(s: Arc<RwLock<GameState>>, mut l: Box<dyn LevelState>, rx: Receiver<UserAction>) -> thread::JoinHandle<()> {
+pub fn spawn_logic_thread(s: Arc<RwLock<GameState>>, mut l: Box<dyn LevelState>, mut controllers: Vec<Box<dyn Controller>>, mut recorder: Option<ReplayRecorder>) -> thread::JoinHandle<()> {
     // Poll the Lazy
     crate::text::GRIDS.len();
 
     thread::spawn(move || {
         let mut pool = Pool::new(NUM_BOARD_ADVANCE_THREADS);
+        let mut turns: [VecDeque<Dir>; MAX_PLAYERS] = Default::default();
         loop {
             let start = Instant::now();
 
+            // Controllers may wait on the network, so they're asked without holding the write lock
+            let actions = gather_actions(&mut controllers, &s.read().unwrap());
+            let Some(actions) = actions.and_then(|actions| sync_actions(&mut controllers, actions)) else {
+                return;
+            };
+
             let processing_time;
             {
                 let lock_start = start.elapsed();
                 let mut s_w = s.write().unwrap();
                 let lock_gotten = start.elapsed();
                 let lock_time = lock_gotten - lock_start;
-                
-                let poisoned = handle_keys(&rx, &mut s_w, &mut l);
-                if poisoned {
-                    return;
-                }
 
+                apply_actions(actions, &mut s_w, &mut l, &mut turns, &mut recorder);
                 advance_board(&mut s_w, &mut *l, &mut pool);
 
                 processing_time = start.elapsed();
@@ -130,113 +177,316 @@ pub fn spawn_logic_thread(s: Arc<RwLock<GameState>>, mut l: Box<dyn LevelState>,
 }
 ///////////////////////////////////////////////////////////
 
-// Returns true if Tx closed
-fn handle_keys(rx: &Receiver<UserAction>, s: &mut GameState, l: &mut Box<dyn LevelState>) -> bool {
-    match rx.try_recv() {
-        Ok(key) => {
-            match key {
-                UserAction::Up => {
-                    if !s.failed {
-                        s.snake.point(Dir::Up)
-                    }
-                }
-                UserAction::Left => {
-                    if !s.failed {
-                        s.snake.point(Dir::Left)
-                    }
-                }
-                UserAction::Down => {
-                    if !s.failed {
-                        s.snake.point(Dir::Down)
-                    }
-                }
-                UserAction::Right => {
-                    if !s.failed {
-                        s.snake.point(Dir::Right)
-                    }
-                }
-                UserAction::ShopItem(shop_item_num) => {
-                    set_shop_item_selected(s, shop_item_num);
+/// Applies what every controller wants to do this tick, in order. Returns true once a controller is gone
+pub fn handle_actions(controllers: &mut [Box<dyn Controller>], s: &mut GameState, l: &mut Box<dyn LevelState>, turns: &mut [VecDeque<Dir>; MAX_PLAYERS], recorder: &mut Option<ReplayRecorder>) -> bool {
+    let Some(actions) = gather_actions(controllers, s).and_then(|actions| sync_actions(controllers, actions)) else {
+        return true;
+    };
+    apply_actions(actions, s, l, turns, recorder);
+    false
+}
+
+/// What each controller wants to do this tick. Returns `None` once a controller is gone
+fn gather_actions(controllers: &mut [Box<dyn Controller>], s: &GameState) -> Option<Vec<Vec<UserAction>>> {
+    controllers.iter_mut().map(|controller| controller.actions(s)).collect()
+}
+
+/// Lets each controller settle its actions with anyone else playing. Returns `None` once a controller is gone
+fn sync_actions(controllers: &mut [Box<dyn Controller>], actions: Vec<Vec<UserAction>>) -> Option<Vec<Vec<UserAction>>> {
+    controllers.iter_mut().zip(actions).map(|(controller, actions)| controller.sync(actions)).collect()
+}
+
+fn apply_actions(actions: Vec<Vec<UserAction>>, s: &mut GameState, l: &mut Box<dyn LevelState>, turns: &mut [VecDeque<Dir>; MAX_PLAYERS], recorder: &mut Option<ReplayRecorder>) {
+    // Turns wait in the queue; everything else happens now
+    for key in actions.into_iter().flatten() {
+        match key {
+            // Saving and loading step outside the run, so they're kept out of the replay.
+            // A replay can't pick up from a loaded game, so recording stops there
+            UserAction::Save => handle_key(key, s, l, turns),
+            UserAction::Load => {
+                if load_quicksave(s, l, turns) && recorder.take().is_some() {
+                    println!("Stopped recording the replay, since it can't include a loaded game");
                 }
-                UserAction::Buy => {
-                    buy(s, &mut **l);
+            }
+            _ => {
+                if let Some(recorder) = recorder {
+                    recorder.record(s.frame_num, key);
                 }
-                UserAction::Restart => if let Some(level_state) = s.next_level() {
-                    *l = level_state
-                },
-                UserAction::Debug => {
-                    s.debug_screen = !s.debug_screen;
+                handle_key(key, s, l, turns);
+            }
+        }
+    }
+
+    // One turn per snake per tick, skipping any that would not change where it is going
+    if !s.failed {
+        for (p, turns) in s.players.iter_mut().zip(turns.iter_mut()) {
+            while let Some(dir) = turns.pop_front() {
+                if p.snake.point(dir) {
+                    break;
                 }
-                UserAction::Quit => {}
+            }
+        }
+    }
+}
+
+fn handle_key(key: UserAction, s: &mut GameState, l: &mut Box<dyn LevelState>, turns: &mut [VecDeque<Dir>; MAX_PLAYERS]) {
+    match key {
+        UserAction::Turn(player, dir) => {
+            let turns = &mut turns[s.player_index(player)];
+            if turns.len() < MAX_QUEUED_TURNS {
+                turns.push_back(dir);
+            }
+        }
+        UserAction::RivalTurn(rival, dir) => {
+            if let Some(r) = s.rivals.get_mut(rival) {
+                r.snake.point(dir);
+            }
+        }
+        UserAction::ShopItem(shop_item_num) => {
+            set_shop_item_selected(s, shop_item_num);
+        }
+        UserAction::Buy(player) => {
+            let player = s.player_index(player);
+            buy(s, &mut **l, player);
+        }
+        UserAction::Activate(player, kind) => {
+            let player = s.player_index(player);
+            activate(s, player, kind);
+        }
+        UserAction::Restart => {
+            turns.iter_mut().for_each(VecDeque::clear);
+            // Move on once the level is complete, otherwise try it again
+            let level_state = if s.final_score.is_some() {
+                s.next_level()
+            } else {
+                s.reset_level()
             };
+            match level_state {
+                Some(level_state) => *l = level_state,
+                None => println!("No more levels. Final score: {}", s.players.iter().map(|p| p.coins).sum::<usize>()),
+            }
+        },
+        UserAction::Debug => {
+            s.debug_screen = !s.debug_screen;
         }
-        Err(TryRecvError::Empty) => (),
-        Err(TryRecvError::Disconnected) => {
-            println!("Tx closed. Exiting thread.");
-            return true;
+        UserAction::Save => match save::save_game(quicksave_path(), s, &**l) {
+            Ok(()) => println!("Saved game to {}", quicksave_path().display()),
+            Err(err) => println!("Unable to save game: {}", err),
+        },
+        UserAction::Load => {
+            load_quicksave(s, l, turns);
         }
+        UserAction::Quit => {}
     }
+}
 
-    false
+/// Replaces the game with the quicksave. Returns whether it loaded
+fn load_quicksave(s: &mut GameState, l: &mut Box<dyn LevelState>, turns: &mut [VecDeque<Dir>; MAX_PLAYERS]) -> bool {
+    match save::load_game(quicksave_path()) {
+        Ok((new_s, new_l)) => {
+            turns.iter_mut().for_each(VecDeque::clear);
+            let debug_screen = s.debug_screen;
+            *s = new_s;
+            s.debug_screen = debug_screen;
+            *l = new_l;
+            println!("Loaded game from {}", quicksave_path().display());
+            true
+        }
+        Err(err) => {
+            println!("Unable to load game: {}", err);
+            false
+        }
+    }
 }
 
 fn set_shop_item_selected(s: &mut GameState, shop_item_num: usize) {
-    if shop_item_num > NUM_SHOP_ITEMS {
+    if shop_item_num >= NUM_SHOP_ITEMS {
         return;
     }
-    s.scoreboard.shop_item_display(&s.shop, s.shop.selected, ShopItemFill::Clear);
+    for p in &mut s.players {
+        p.scoreboard.shop_item_display(&s.shop, s.shop.selected, ShopItemFill::Clear);
+    }
     s.shop.selected = shop_item_num;
-    s.scoreboard.shop_item_display(&s.shop, s.shop.selected, ShopItemFill::FromItem);
+    for p in &mut s.players {
+        p.scoreboard.shop_item_display(&s.shop, s.shop.selected, ShopItemFill::FromItem);
+    }
 }
 
-fn buy(s: &mut GameState, l: &mut dyn LevelState) {
+fn buy(s: &mut GameState, l: &mut dyn LevelState, player: usize) {
     let ShopItem { kind, mut price } = s.shop.get_selected();
     price *= s.shop.price_multiplier;
     
-    println!("Buy {:?}! Current coins: {}. Cost: {}", kind, s.coins, price);
-    if price > s.coins {
+    let p = &mut s.players[player];
+    println!("Player {} buys {:?}! Current coins: {}. Cost: {}", player + 1, kind, p.coins, price);
+    if price > p.coins {
         return;
     }
-    s.scoreboard.coins_remove(s.coins);
-    s.coins -= price;
-    s.scoreboard.coins(s.coins);
+    p.scoreboard.coins_remove(p.coins);
+    p.coins -= price;
+    p.scoreboard.coins(p.coins);
+
+    p.scoreboard.inventory_remove(&p.inventory);
+    p.inventory[kind as usize] += 1;
+    p.scoreboard.inventory(&p.inventory);
+    
+    for p in &mut s.players {
+        p.scoreboard.shop_remove(&s.shop);
+    }
+    l.reset_shop(s);
+    // Prices are locked in when the shop loads
+    s.shop.price_multiplier = s.price_multiplier;
+    for p in &mut s.players {
+        p.scoreboard.shop(&s.shop);
+    }
+}
 
+/// Uses one of the player's powerups at their head
+fn activate(s: &mut GameState, player: usize, kind: PowerupType) {
+    if s.failed || s.final_score.is_some() {
+        return;
+    }
+    let p = &mut s.players[player];
+    if kind == PowerupType::Shovel && p.dirt > 0 {
+        drop_dirt(s, player);
+        return;
+    }
+    if p.inventory[kind as usize] == 0 {
+        return;
+    }
+    // A shovel is only used up if there was something to dig
+    if kind == PowerupType::Shovel && dig(s, player) == 0 {
+        println!("Player {} has nothing to dig here", player + 1);
+        return;
+    }
+    let p = &mut s.players[player];
+    println!("Player {} activates {:?}!", player + 1, kind);
+    p.scoreboard.inventory_remove(&p.inventory);
+    p.inventory[kind as usize] -= 1;
+    p.scoreboard.inventory(&p.inventory);
+
+    let head = p.snake.head_pos();
     match kind {
-        super::types::PowerupType::Water => {
-            s.board.explosion(s.snake.head_pos(), PlusWater(1));
+        PowerupType::Water => {
+            s.board.explosion(head, PlusWater(1), &mut s.rng);
         },
-        super::types::PowerupType::Explosive => {
-            println!("Explosive not implemented yet. Doing nothing.");
+        PowerupType::Explosive => {
+            s.board.crater(head, MinusElev(1), &mut s.rng);
         },
-        super::types::PowerupType::Shovel => {
-            println!("Shovel not implemented yet. Doing nothing.");
+        PowerupType::Shovel => {},
+        PowerupType::Seed => {
+            s.board.explosion(head, PlusSeed(1), &mut s.rng);
         },
-        super::types::PowerupType::Seed => {
-            s.board.explosion(s.snake.head_pos(), PlusSeed(1));
-        },
-        super::types::PowerupType::Invincibility => {
-            s.invinc_time += INVINC_TIME;
+        PowerupType::Invincibility => {
+            p.invinc_time += INVINC_TIME;
         },
     }
-    
-    s.scoreboard.shop_remove(&s.shop);
-    l.reset_shop(s);
-    s.scoreboard.shop(&s.shop);
 }
 
-// Returns true if failed
-fn advance_board(s: &mut GameState, l: &mut dyn LevelState, pool: &mut Pool) {
-    if s.failed {
+fn inflate(s: &mut GameState) {
+    if s.rng.gen_ratio(1, s.level.inflation_one_in) {
+        for p in &mut s.players {
+            p.scoreboard.pm_remove(s.price_multiplier);
+        }
+        s.price_multiplier += 1;
+        for p in &mut s.players {
+            p.scoreboard.pm(s.price_multiplier);
+        }
+    }
+}
+
+/// Digs up the highest ground around the player's head. Returns how much was dug
+fn dig(s: &mut GameState, player: usize) -> usize {
+    let highest = disk(s.players[player].snake.head_pos(), SHOVEL_RADIUS)
+        .filter(|&coord| s.board.cell_at(coord).obj != CellObject::Border)
+        .max_by_key(|&coord| s.board.cell_at(coord).elev);
+    let Some(coord) = highest else {
+        return 0;
+    };
+    let cell = s.board.cell_at_mut(coord);
+    let dug = cell.elev.min(SHOVEL_DIRT);
+    cell.elev -= dug;
+    if dug == 0 {
+        return 0;
+    }
+    println!("Player {} dug up {} dirt", player + 1, dug);
+
+    let p = &mut s.players[player];
+    p.scoreboard.dirt_remove(p.dirt);
+    p.dirt += dug as usize;
+    p.scoreboard.dirt(p.dirt);
+    dug as usize
+}
+
+/// Spreads the player's carried dirt evenly over the cells around their head
+fn drop_dirt(s: &mut GameState, player: usize) {
+    let p = &mut s.players[player];
+    if p.dirt == 0 {
+        return;
+    }
+    let cells: Vec<Coord> = disk(p.snake.head_pos(), SHOVEL_RADIUS)
+        .filter(|&coord| s.board.cell_at(coord).obj != CellObject::Border)
+        .collect();
+    if cells.is_empty() {
+        return;
+    }
+
+    p.scoreboard.dirt_remove(p.dirt);
+    let (share, extra) = (p.dirt / cells.len(), p.dirt % cells.len());
+    let mut dropped = 0;
+    for (i, coord) in cells.into_iter().enumerate() {
+        let amount = share + (i < extra) as usize;
+        let cell = s.board.cell_at_mut(coord);
+        let amount = amount.min((u8::MAX - cell.elev) as usize);
+        cell.elev += amount as u8;
+        dropped += amount;
+    }
+    println!("Player {} dropped {} dirt", player + 1, dropped);
+    p.dirt -= dropped;
+    p.scoreboard.dirt(p.dirt);
+}
+
+pub fn advance_board(s: &mut GameState, l: &mut dyn LevelState, pool: &mut Pool) {
+    if s.failed || s.final_score.is_some() {
         return;
     }
 
-    // Advance snake
-    s.snake.advance();
+    // Advance snakes. One that runs into its own body bites off everything from there back
+    let tails: Vec<Vec<Coord>> = s.players.iter_mut().enumerate().map(|(player, p)| {
+        let (left, bitten) = advance_snake(&mut p.snake);
+        if bitten != 0 {
+            println!("Player {} bit off {} of its own tail. Score: {}", player + 1, bitten, p.snake.len());
+        }
+        left
+    }).collect();
+    let rival_tails: Vec<Vec<Coord>> = s.rivals.iter_mut().map(|r| advance_snake(&mut r.snake).0).collect();
 
-    // Check what we hit
-    let head_pos = s.snake.head_pos();
-    handle_hit(s.board.cell_at(head_pos), s);
-    s.board.pt(head_pos, CellObject::Wall);
+    // Check what we hit. Heads that meet hit each other like a wall, whichever snake is checked first
+    let heads: Vec<Coord> = s.players.iter().map(|p| p.snake.head_pos())
+        .chain(s.rivals.iter().map(|r| r.snake.head_pos()))
+        .collect();
+    let hit_cell = |s: &GameState, i: usize| {
+        let cell = s.board.cell_at(heads[i]);
+        let head_on = heads.iter().enumerate().any(|(j, &head)| j != i && head == heads[i]);
+        if head_on { CellState { obj: CellObject::Wall, ..cell } } else { cell }
+    };
+    for player in 0..s.players.len() {
+        handle_hit(hit_cell(s, player), s, player);
+    }
+    let mut dead_rivals = Vec::new();
+    for rival in 0..s.rivals.len() {
+        let cell = hit_cell(s, s.players.len() + rival);
+        // A dead rival leaves the cell it died on as it was
+        if lethal_hit(cell, 0).is_some() {
+            dead_rivals.push(rival);
+        } else if let CellObject::Food(..) = cell.obj {
+            s.rivals[rival].snake.add_food(1);
+        }
+    }
+    for (i, &head) in heads.iter().enumerate() {
+        if i < s.players.len() || !dead_rivals.contains(&(i - s.players.len())) {
+            s.board.pt(head, CellObject::Wall);
+        }
+    }
 
     // Update all cells
     let mut board_new = s.board.clone();
@@ -273,42 +523,188 @@ fn advance_board(s: &mut GameState, l: &mut dyn LevelState, pool: &mut Pool) {
     });
     s.board = board_new;
 
+    harvest(s);
+    check_forest(s);
+
     l.update(s);
+    inflate(s);
 
     // Decrement powerup
-    if s.invinc_time != 0 {
-        s.invinc_time -= 1;
-        if s.invinc_time == 0 {
-            println!("Invincibility over");
+    for (player, p) in s.players.iter_mut().enumerate() {
+        if p.invinc_time != 0 {
+            p.invinc_time -= 1;
+            if p.invinc_time == 0 {
+                println!("Player {}'s invincibility is over", player + 1);
+            }
         }
     }
 
     s.frame_num += 1;
 
-    s.board.pt(head_pos, CellObject::Snake(super::types::SnakeColor::Head, s.snake.len()));
+    let secs_left = s.time_left_secs();
+    s.level_time += 1;
+    let new_secs_left = s.time_left_secs();
+    if new_secs_left != secs_left {
+        for p in &mut s.players {
+            p.scoreboard.time_remove(secs_left);
+            p.scoreboard.time(new_secs_left);
+        }
+    }
+
+    for (player, tail) in tails.into_iter().enumerate() {
+        place_snake(s, player, tail);
+    }
+    for (rival, tail) in rival_tails.into_iter().enumerate() {
+        clear_vacated(s, tail);
+        if !dead_rivals.contains(&rival) {
+            draw_snake(&mut s.board, &s.rivals[rival].snake, RIVAL_SNAKE_COLORS);
+        }
+    }
+    for rival in dead_rivals.into_iter().rev() {
+        kill_rival(s, rival);
+    }
+
+    if !s.failed && (s.level_time >= s.level.time_limit || l.is_complete(s)) {
+        complete_level(s);
+    }
 }
 
-fn handle_hit(cell: CellState, s: &mut GameState) {
-    // Handle failing separately
-    match cell {
-        CellState { floor: CellFloor::Lava { .. }, .. } |
-        CellState { obj: CellObject::Wall, .. } => {
-            if s.invinc_time == 0 {
-                fail(s, "Hit wall or lava!");
-            }
+/// Ends the level, cashing each snake's length in for coins
+fn complete_level(s: &mut GameState) {
+    let mut total = 0;
+    for (player, p) in s.players.iter_mut().enumerate() {
+        let length_value = p.snake.len() * COINS_PER_LENGTH;
+        let score = p.coins + length_value;
+        println!("Player {}: {} coins + {} length * {} = {} points", player + 1, p.coins, p.snake.len(), COINS_PER_LENGTH, score);
+
+        p.scoreboard.coins_remove(p.coins);
+        p.coins = score;
+        p.scoreboard.coins(p.coins);
+        total += score;
+    }
+    println!("Level complete! {} points", total);
+
+    for player in 0..s.players.len() {
+        s.players[player].scoreboard.score(total);
+        let dropped = s.players[player].snake.shrink_to(START_SNAKE_LEN);
+        place_snake(s, player, dropped);
+    }
+    s.final_score = Some(total);
+    println!("Press F to continue.");
+}
+
+/// Plants quietly earn coins, more the taller they are. Every snake, rivals included, gets an even share
+fn harvest(s: &mut GameState) {
+    let income: usize = s.board
+        .cells()
+        .map(|cell| match cell.floor {
+            CellFloor::Seed { height, .. } if height >= MATURE_SEED_HEIGHT => height as usize,
+            _ => 0,
+        })
+        .sum();
+    let share = income / (s.players.len() + s.rivals.len());
+
+    for p in &mut s.players {
+        p.coin_progress += share;
+        let earned = p.coin_progress / INCOME_PER_COIN;
+        if earned != 0 {
+            p.coin_progress %= INCOME_PER_COIN;
+            p.scoreboard.coins_remove(p.coins);
+            p.coins += earned;
+            p.scoreboard.coins(p.coins);
         }
-        CellState { obj: CellObject::Border, .. } => {
-            // Fail even with invincibility
-            fail(s, "Hit border!");
+    }
+    for r in &mut s.rivals {
+        r.coin_progress += share;
+        r.coins += r.coin_progress / INCOME_PER_COIN;
+        r.coin_progress %= INCOME_PER_COIN;
+    }
+}
+
+/// Fails the run once the last plant is gone
+fn check_forest(s: &mut GameState) {
+    let seed_count = s.board
+        .cells()
+        .filter(|cell| matches!(cell.floor, CellFloor::Seed { .. } | CellFloor::Fire { .. }))
+        .count();
+    if s.seed_count != 0 && seed_count == 0 {
+        fail(s, FailReason::LastSeedDied);
+    }
+    s.seed_count = seed_count;
+}
+
+/// Moves `snake` on, returning the cells it left and how many of them it bit off its own tail
+fn advance_snake(snake: &mut Snake) -> (Vec<Coord>, usize) {
+    let mut left: Vec<Coord> = snake.advance().into_iter().collect();
+    let bitten = snake.bite_tail();
+    let num_bitten = bitten.len();
+    left.extend(bitten);
+    (left, num_bitten)
+}
+
+/// Clears the cells in `left` that the player's snake has moved off of and draws it where it is now
+fn place_snake(s: &mut GameState, player: usize, left: impl IntoIterator<Item = Coord>) {
+    clear_vacated(s, left);
+    draw_snake(&mut s.board, &s.players[player].snake, PLAYER_SNAKE_COLORS[player]);
+}
+
+/// Clears the cells in `left` that no snake covers any more
+fn clear_vacated(s: &mut GameState, left: impl IntoIterator<Item = Coord>) {
+    for coord in left {
+        if !is_covered(s, coord) && matches!(s.board.cell_at(coord).obj, CellObject::Snake(..)) {
+            s.board.pt(coord, CellObject::None);
+        }
+    }
+}
+
+fn is_covered(s: &GameState, coord: Coord) -> bool {
+    s.players.iter().map(|p| &p.snake)
+        .chain(s.rivals.iter().map(|r| &r.snake))
+        .any(|snake| snake.covers(coord))
+}
+
+fn draw_snake(board: &mut Board, snake: &Snake, [head, dark, light]: [SnakeColor; 3]) {
+    for (i, (coord, part)) in snake.parts().enumerate() {
+        let color = match i {
+            0 => head,
+            i if i % 2 == 1 => dark,
+            _ => light,
+        };
+        board.pt(coord, CellObject::Snake(color, part));
+    }
+}
+
+/// Takes a rival off the board, leaving the body it had before its fatal move behind as food
+fn kill_rival(s: &mut GameState, rival: usize) {
+    let r = s.rivals.remove(rival);
+    println!("A rival died at length {}", r.snake.len());
+    for coord in r.snake.body().skip(1) {
+        if !is_covered(s, coord) && matches!(s.board.cell_at(coord).obj, CellObject::Snake(..)) {
+            s.board.pt(coord, CellObject::Food(FOOD_LIFE));
         }
-        _ => {}
+    }
+}
+
+/// Why running into `cell` kills a snake, if it does
+fn lethal_hit(cell: CellState, invinc_time: usize) -> Option<FailReason> {
+    if !is_lethal(cell, invinc_time != 0) {
+        return None;
+    }
+    Some(if cell.obj == CellObject::Border { FailReason::HitBorder } else { FailReason::HitWallOrLava })
+}
+
+fn handle_hit(cell: CellState, s: &mut GameState, player: usize) {
+    // Handle failing separately
+    if let Some(reason) = lethal_hit(cell, s.players[player].invinc_time) {
+        fail(s, reason);
     }
     match cell {
         // Safe conditions
         CellState { obj: CellObject::None | CellObject::Snake(_, _), .. } => {}
         CellState { obj: CellObject::Food(..), .. } => {
-            println!("Ate food. Score: {}", s.snake.len());
-            s.snake.add_food(1);
+            let snake = &mut s.players[player].snake;
+            snake.add_food(1);
+            println!("Player {} ate food. Score: {}", player + 1, snake.len());
         }
         // Fail conditions - handled above - put last so that you can still get powerup on lava
         CellState { floor: CellFloor::Lava { .. }, .. } |
@@ -322,7 +718,29 @@ pub fn tick(old_cell: &CellState, old_surrounding: [&CellState; 8], new_cell: &m
     liquid_flow(&new_cell.clone(), &old_surrounding, new_cell, coord, s);
 }
 
-fn tick_floor(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell: &mut CellState, _coord: Coord, _s: &GameState) {
+/// The denominator of the chance that a burning neighbor sets a seed alight each tick
+const FIRE_SPREAD_ONE_IN: u32 = 400;
+/// Every this much fuel a fire has makes it more likely to spread
+const FIRE_FUEL_PER_SPREAD: u32 = 4;
+
+/// The denominator of the chance that mature plants next to an empty cell drop food on it each tick
+const FOOD_SPAWN_ONE_IN: u32 = 20_000;
+/// The number of ticks food lasts before it rots
+const FOOD_LIFE: usize = 300;
+/// The total height of mature plants, summed over ticks, that earns one coin
+const INCOME_PER_COIN: usize = 5_000;
+
+/// The chance (1 in this many) that a seed growing taller uses up a unit of fertility
+const GROWTH_FERTILITY_COST_ONE_IN: u32 = 3;
+/// Fertility returned to the soil when a plant dies
+const COMPOST_FERTILITY: i8 = 1;
+/// Fertility left behind by ash when a fire burns out
+const ASH_FERTILITY: i8 = 2;
+/// The chance (1 in this many) that water adds a unit of fertility to the ground under it each tick
+const FLOOD_FERTILITY_ONE_IN: u32 = 500;
+
+fn tick_floor(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell: &mut CellState, coord: Coord, s: &GameState) {
+    let mut rng = get_local_rng(coord, RngStream::Floor, s);
     match old_cell.floor {
         CellFloor::Empty | CellFloor::Indicator(..) => {
             // Find a cell that wants to spread to this cell
@@ -338,16 +756,45 @@ fn tick_floor(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell:
                 });
             // At least one seed would like to spread to this cell
             if let Some(saturation) = saturation {
-                if rand::thread_rng().gen_range(0..100) == 0 {
+                if rng.gen_range(0..100) == 0 {
                     new_cell.update(CellFloor::Seed { height: 1, saturation });
                 }
             }
         }
         CellFloor::Water { .. } => {
+            // Floods leave silt behind
+            if rng.gen_range(0..FLOOD_FERTILITY_ONE_IN) == 0 {
+                new_cell.add_fertility(1);
+            }
         }
         CellFloor::Lava { .. } => {
         }
+        CellFloor::Fire { fuel } => {
+            // Water puts the fire out, saving what is left of the plant
+            if old_surrounding.iter().any(|cell| matches!(cell.floor, CellFloor::Water { .. })) {
+                new_cell.update(CellFloor::Seed { height: fuel, saturation: MAX_SATURATION });
+            } else if fuel > 1 {
+                new_cell.update(CellFloor::Fire { fuel: fuel - 1 });
+            } else {
+                new_cell.update(CellFloor::Empty);
+                new_cell.add_fertility(ASH_FERTILITY);
+            }
+        }
         CellFloor::Seed { height, saturation } => {
+            // Each burning neighbor may set this plant alight. Tall fires spread further, and dry plants catch faster
+            let dryness = (MAX_SATURATION - saturation.max(0)) as u32;
+            let ignite_chance: u32 = old_surrounding
+                .iter()
+                .map(|cell| match cell.floor {
+                    CellFloor::Fire { fuel } => (1 + fuel as u32 / FIRE_FUEL_PER_SPREAD) * (1 + dryness),
+                    _ => 0,
+                })
+                .sum();
+            if ignite_chance != 0 && rng.gen_range(0..FIRE_SPREAD_ONE_IN) < ignite_chance {
+                new_cell.update(CellFloor::Fire { fuel: height });
+                return;
+            }
+
             let mut new_saturation = saturation - 1;
             for cell in old_surrounding {
                 match cell.floor {
@@ -369,36 +816,43 @@ fn tick_floor(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell:
             let max_height = if max_height < 0 { 0 } else { max_height as u8 };
             let mut new_height = height.min(max_height);
 
-            if new_height != max_height && rand::thread_rng().gen_range(0..100) == 0 {
+            if new_height != max_height && rng.gen_range(0..100) == 0 {
                 new_height += 1;
+                if rng.gen_range(0..GROWTH_FERTILITY_COST_ONE_IN) == 0 {
+                    new_cell.add_fertility(-1);
+                }
             }
             if new_height != 0 {
                 new_cell.update(CellFloor::Seed { height: new_height, saturation: new_saturation });
             } else {
+                // The dead plant composts back into the soil
                 new_cell.update(CellFloor::Empty);
+                new_cell.add_fertility(COMPOST_FERTILITY);
             }
         }
     }
 }
 
 
-fn tick_object(old_cell: &CellState, _old_surrounding: &[&CellState; 8], new_cell: &mut CellState, _coord: Coord, _s: &GameState) {
+fn tick_object(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell: &mut CellState, coord: Coord, s: &GameState) {
     match old_cell.obj {
-        CellObject::None => {}
-        CellObject::Wall => {} // Conversion to water or lava is handled by CellFloor::Empty above
-        CellObject::Snake(color, life) => {
-            // println!("ticking snake {:?}", old_cell);
-            if life >= 1 {
-                let new_color = match color {
-                    SnakeColor::Head | SnakeColor::LightRed => SnakeColor::DarkRed,
-                    SnakeColor::DarkRed => SnakeColor::LightRed,
-                };
-                new_cell.update(CellObject::Snake(new_color, life - 1));
-            } else {
-                new_cell.update(CellObject::None);
+        CellObject::None => {
+            // Mature plants drop food next to them. Taller plants drop more
+            if let CellFloor::Empty | CellFloor::Seed { .. } = old_cell.floor {
+                let food_chance: u32 = old_surrounding
+                    .iter()
+                    .map(|cell| match cell.floor {
+                        CellFloor::Seed { height, .. } if height >= MATURE_SEED_HEIGHT => height as u32,
+                        _ => 0,
+                    })
+                    .sum();
+                if food_chance != 0 && get_local_rng(coord, RngStream::Object, s).gen_range(0..FOOD_SPAWN_ONE_IN) < food_chance {
+                    new_cell.update(CellObject::Food(FOOD_LIFE));
+                }
             }
-            // println!("new snake object: {:?}", new_cell);
         }
+        CellObject::Wall => {} // Conversion to water or lava is handled by CellFloor::Empty above
+        CellObject::Snake(..) => {} // Drawn from the snake's body by `place_snake`
         CellObject::Food(life) => {
             if life >= 1 {
                 new_cell.update(CellObject::Food(life - 1));
@@ -413,9 +867,18 @@ fn tick_object(old_cell: &CellState, _old_surrounding: &[&CellState; 8], new_cel
 const SURROUNDING_COORDS: [(isize, isize); 8] = [
     (-1, -1), ( 0, -1), ( 1, -1),
     (-1,  0),           ( 1,  0),
-    ( 1, -1), ( 0,  1), ( 1,  1),
+    (-1,  1), ( 0,  1), ( 1,  1),
 ];
 
+/// The chance (1 in this many) that a unit of flowing water wears a unit of elevation off the cell it leaves
+const EROSION_ONE_IN: u32 = 20;
+/// How much sediment still water drops per tick
+const SEDIMENT_DEPOSIT_RATE: u8 = 1;
+/// Water this shallow or shallower can evaporate
+const EVAPORATION_MAX_DEPTH: u8 = 1;
+/// The chance (1 in this many) that shallow water receiving no inflow loses a unit of depth each tick
+const EVAPORATION_ONE_IN: u32 = 300;
+
 fn liquid_flow(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell: &mut CellState, coord: Coord, s: &GameState) {
     // To give liquid
     // 1. For this cell, create a rng seeded with the hash of (coord, frame num, salt). 
@@ -432,24 +895,31 @@ fn liquid_flow(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell
         return;
     }
 
-    // If we are seed, and we are touching lava, become lava
+    // If we are seed, and we are touching lava, catch fire
     // This could destroy liquid that flows into this block on the same tick, but that's fine
     for cell in old_surrounding {
-        if let (CellFloor::Seed { .. }, CellFloor::Lava { depth }) = (old_cell.floor, cell.floor) {
-            new_cell.update(CellFloor::Lava { depth });
+        if let (CellFloor::Seed { height, .. }, CellFloor::Lava { .. }) = (old_cell.floor, cell.floor) {
+            new_cell.update(CellFloor::Fire { fuel: height });
             return;
         }
     }
 
     // Give liquid
     let mut floor = old_cell.floor; // make mutable copy
-    if let CellFloor::Water { depth } | CellFloor::Lava { depth } = &mut floor {
-        let mut self_rng = get_local_rng(coord, s);
+    let mut elev = old_cell.elev;
+    let mut sediment = if let CellFloor::Water { sediment, .. } = old_cell.floor { sediment } else { 0 };
+    let mut gave_liquid = false;
+    if let CellFloor::Water { depth, .. } | CellFloor::Lava { depth, .. } = &mut floor {
+        let mut self_rng = get_local_rng(coord, RngStream::LiquidFlow, s);
         let to = self_rng.gen_range(0..8usize);
         let to = old_surrounding[to];
         if can_liquid_flow(to, old_cell) {
             // We can give one away, so minus one
             *depth -= 1;
+            gave_liquid = true;
+            let (carried, eroded) = sediment_transport(old_cell, &mut self_rng);
+            sediment -= carried;
+            elev -= eroded;
         }
     }
 
@@ -475,26 +945,43 @@ fn liquid_flow(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell
             continue;
         }
         if let (liquid, _, CellFloor::Water { .. }) | (_, liquid, CellFloor::Lava { .. }) = (&mut water, &mut lava, from.floor) {
-            let mut giver_rng = get_local_rng(coord, s);
+            let mut giver_rng = get_local_rng(coord, RngStream::LiquidFlow, s);
             let to_receive = giver_rng.gen_range(0..8usize);
             if to_receive == 7 - i {
                 *liquid += 1;
+                // The giver makes the same draws from the same rng, so we agree on what it sent
+                let (carried, eroded) = sediment_transport(from, &mut giver_rng);
+                sediment = sediment.saturating_add(carried + eroded);
             }
         }
     }
 
-    if let (liquid, _, CellFloor::Water { depth }) | (_, liquid, CellFloor::Lava { depth }) = (&mut water, &mut lava, floor) {
+    let (water_in, lava_in) = (water, lava);
+
+    if let (liquid, _, CellFloor::Water { depth, .. }) | (_, liquid, CellFloor::Lava { depth, .. }) = (&mut water, &mut lava, floor) {
         *liquid = liquid.saturating_add(depth)
     }
 
-    let elev;
     match water.cmp(&lava) {
         std::cmp::Ordering::Less => {
-            elev = old_cell.elev.saturating_add(water);
-            floor = CellFloor::Lava { depth: lava - water };
+            elev = elev.saturating_add(water);
+            // Lava that moved stays hot; lava that sat still cools, one layer at a time
+            let heat = match old_cell.floor {
+                CellFloor::Lava { heat, .. } if !gave_liquid && lava_in == 0 => heat.saturating_sub(1),
+                _ => LAVA_MAX_HEAT,
+            };
+            floor = if heat == 0 {
+                elev = elev.saturating_add(1);
+                match lava - water - 1 {
+                    0 => CellFloor::Empty,
+                    depth => CellFloor::Lava { depth, heat: LAVA_MAX_HEAT },
+                }
+            } else {
+                CellFloor::Lava { depth: lava - water, heat }
+            };
         },
         std::cmp::Ordering::Equal => {
-            elev = old_cell.elev.saturating_add(water);
+            elev = elev.saturating_add(water);
             // If this cell was water or lava, it is now empty
             // otherwise, it just keeps its state
             if let CellFloor::Water { .. } | CellFloor::Lava { .. } = old_cell.floor {
@@ -504,14 +991,43 @@ fn liquid_flow(old_cell: &CellState, old_surrounding: &[&CellState; 8], new_cell
             }
         },
         std::cmp::Ordering::Greater => {
-            elev = old_cell.elev.saturating_add(lava);
-            floor = CellFloor::Water { depth: water - lava };
+            elev = elev.saturating_add(lava);
+            let mut depth = water - lava;
+            if water_in == 0 && depth <= EVAPORATION_MAX_DEPTH {
+                let mut evaporation_rng = get_local_rng(coord, RngStream::Evaporation, s);
+                if evaporation_rng.gen_range(0..EVAPORATION_ONE_IN) == 0 {
+                    depth -= 1;
+                }
+            }
+            floor = if depth == 0 { CellFloor::Empty } else { CellFloor::Water { depth, sediment: 0 } };
         },
     }
 
+    // Settle sediment. Still water drops some of its load, water can't carry more
+    // than its depth, and anything that is no longer water drops all of it.
+    let mut deposit = sediment;
+    if let CellFloor::Water { depth, sediment: new_sediment } = &mut floor {
+        let settled = if !gave_liquid { sediment.min(SEDIMENT_DEPOSIT_RATE) } else { 0 };
+        deposit = settled.max(sediment.saturating_sub(*depth));
+        *new_sediment = sediment - deposit;
+    }
+    elev = elev.saturating_add(deposit);
+
     new_cell.update((floor, elev));
 }
 
+/// The `(carried, eroded)` sediment a unit of flowing liquid takes with it from `from`
+#[inline(always)]
+fn sediment_transport(from: &CellState, giver_rng: &mut impl Rng) -> (u8, u8) {
+    if let CellFloor::Water { sediment, .. } = from.floor {
+        let carried = sediment.min(1);
+        let eroded = (from.elev > 0 && giver_rng.gen_range(0..EROSION_ONE_IN) == 0) as u8;
+        (carried, eroded)
+    } else {
+        (0, 0)
+    }
+}
+
 #[inline(always)]
 fn can_participate_in_liquid_flow(cell: &CellState) -> bool {
     cell.obj != CellObject::Border
@@ -529,16 +1045,30 @@ fn can_liquid_flow(to: &CellState, from: &CellState) -> bool {
     to.roof() < from.roof()
 }
 
+/// Separates the random decisions made for the same cell on the same frame
+#[derive(Clone, Copy)]
+enum RngStream {
+    LiquidFlow,
+    Floor,
+    Object,
+    Evaporation,
+}
+
+/// The key is spelled out byte by byte rather than hashed, so runs don't change with the std or rand version
 #[inline(always)]
-fn get_local_rng(coord: Coord, s: &GameState) -> impl Rng {
-    let mut hasher = DefaultHasher::new();
-    (coord, s.frame_num, s.salt).hash(&mut hasher);
-    let hash = hasher.finish();
-    rand::rngs::StdRng::seed_from_u64(hash)
+fn get_local_rng(coord: Coord, stream: RngStream, s: &GameState) -> impl Rng {
+    let mut key = [0; 32];
+    key[0..8].copy_from_slice(&(coord.x as u64).to_le_bytes());
+    key[8..16].copy_from_slice(&(coord.y as u64).to_le_bytes());
+    key[16..24].copy_from_slice(&(s.frame_num as u64).to_le_bytes());
+    key[24..28].copy_from_slice(&s.salt.to_le_bytes());
+    let mut rng = GameRng::from_seed(key);
+    rng.set_stream(stream as u64);
+    rng
 }
 
 fn _place_debug(board: &mut Board) {
-    board.pt((10, 10), CellFloor::Water { depth: 200 });
+    board.pt((10, 10), CellFloor::Water { depth: 200, sediment: 0 });
 
     // for i in 2..=10 {
     //     board.pt((5 * i, 10), CellFloor::Empty);
@@ -561,23 +1091,95 @@ fn _place_debug(board: &mut Board) {
     // }
 }
 
-fn fail(s: &mut GameState, message: &str) {
+fn fail(s: &mut GameState, reason: FailReason) {
     s.failed = true;
-    println!("{}", message);
+    s.fail_reason = Some(reason);
+    println!("{}", reason.message());
     println!("Failed. Press F to pay respects.");
 }
 
 #[derive(Clone, Copy, Hash, PartialEq, Debug)]
 pub enum UserAction {
-    Up,
-    Left,
-    Down,
-    Right,
+    /// Turns the given player's snake
+    Turn(usize, Dir),
+    /// Turns the given rival's snake
+    RivalTurn(usize, Dir),
 
     ShopItem(usize),
-    Buy,
+    Buy(usize),
 
     Restart,
     Quit,
     Debug,
+
+    Save,
+    Load,
+
+    Activate(usize, PowerupType),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::hash_map::DefaultHasher, hash::{Hash, Hasher}};
+
+    use super::*;
+    use super::super::{
+        controller::{BotController, RivalController},
+        test_util::{play_frames, TUTORIAL, VOLCANO},
+    };
+
+    /// Plays `ticks` frames from `seed` with a bot and the rivals steering, and hashes the board at the end
+    fn board_hash_after(seed: u64, ticks: usize) -> u64 {
+        let (mut s, mut l) = reset_with_seed(seed, VOLCANO, 1);
+        let mut controllers: Vec<Box<dyn Controller>> = vec![
+            Box::new(BotController::new(0, seed)),
+            Box::new(RivalController::new(seed)),
+        ];
+        play_frames(&mut s, &mut l, &mut controllers, ticks, |_| {});
+        let mut hasher = DefaultHasher::new();
+        s.board.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same() {
+        assert_eq!(board_hash_after(42, 40), board_hash_after(42, 40));
+        assert_ne!(board_hash_after(42, 40), board_hash_after(43, 40));
+    }
+
+    #[test]
+    fn heads_that_meet_hit_each_other() {
+        let (mut s, mut l) = reset_with_seed(1, TUTORIAL, 2);
+        s.rivals.clear();
+        for x in 9..=13 {
+            *s.board.cell_at_mut(Coord { x, y: 10 }) = CellState { floor: CellFloor::Empty, obj: CellObject::None, elev: 0, fertility: 0 };
+        }
+        s.players[0].snake = Snake::new(Coord { x: 10, y: 10 }, Dir::Right, 1);
+        s.players[1].snake = Snake::new(Coord { x: 12, y: 10 }, Dir::Left, 1);
+        // Only the player checked second is safe, so this fails only if the first one sees the second
+        s.players[1].invinc_time = 10;
+
+        advance_board(&mut s, &mut *l, &mut Pool::new(NUM_BOARD_ADVANCE_THREADS));
+        assert!(s.failed);
+    }
+
+    #[test]
+    fn shovel_digs_a_unit_and_is_kept_when_there_is_nothing_to_dig() {
+        let (mut s, _) = reset_with_seed(1, TUTORIAL, 1);
+        let head = s.players[0].snake.head_pos();
+        for coord in disk(head, SHOVEL_RADIUS) {
+            s.board.cell_at_mut(coord).elev = 0;
+        }
+        s.players[0].inventory[PowerupType::Shovel as usize] = 2;
+
+        activate(&mut s, 0, PowerupType::Shovel);
+        assert_eq!(s.players[0].inventory[PowerupType::Shovel as usize], 2);
+        assert_eq!(s.players[0].dirt, 0);
+
+        s.board.cell_at_mut(head).elev = 4;
+        activate(&mut s, 0, PowerupType::Shovel);
+        assert_eq!(s.players[0].inventory[PowerupType::Shovel as usize], 1);
+        assert_eq!(s.players[0].dirt, 1);
+        assert_eq!(s.board.cell_at(head).elev, 3);
+    }
 }
@@ -3,4 +3,15 @@ pub mod draw;
 pub mod types;
 pub mod art;
 pub mod levels;
-pub mod scoreboard;
\ No newline at end of file
+pub mod scoreboard;
+pub mod replay;
+pub mod headless;
+pub mod bytes;
+pub mod save;
+pub mod rival;
+pub mod controller;
+pub mod path;
+pub mod net;
+
+#[cfg(test)]
+mod test_util;
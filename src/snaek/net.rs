@@ -0,0 +1,281 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use super::{
+    bytes::{ByteReader, ByteWriter, invalid_data},
+    controller::Controller,
+    levels::LEVELS,
+    logic::UserAction,
+    replay::{action_from_bytes, action_to_bytes},
+    types::{GameState, MAX_PLAYERS},
+};
+
+const NET_MAGIC: &[u8; 4] = b"SNKN";
+const NET_VERSION: u8 = 1;
+/// Anything longer than this isn't a message we sent
+const MAX_MESSAGE_LEN: usize = 1 << 20;
+/// How long to wait on another instance before giving up on the game
+const NET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What every instance needs to build the same starting `GameState`
+pub struct NetGame {
+    pub seed: u64,
+    pub level_index: usize,
+    pub num_players: usize,
+}
+
+/// The connections to the other instances. The host is player 0 and relays for the rest
+pub struct NetLink {
+    player: usize,
+    peers: Peers,
+}
+enum Peers {
+    /// One stream per other player, in player order
+    Host(Vec<TcpStream>),
+    Client(TcpStream),
+}
+
+/// Waits on `port` until every other player has joined, then tells them which game to build
+pub fn host(port: u16, game: &NetGame) -> io::Result<NetLink> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("Waiting for {} more players on port {}", game.num_players - 1, port);
+    accept_players(listener, game)
+}
+
+fn accept_players(listener: TcpListener, game: &NetGame) -> io::Result<NetLink> {
+    let mut clients = Vec::new();
+    for player in 1..game.num_players {
+        let (mut stream, addr) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(NET_TIMEOUT))?;
+        let mut w = ByteWriter::new();
+        w.bytes(NET_MAGIC);
+        w.u8(NET_VERSION);
+        w.u64(game.seed);
+        w.usize(game.level_index);
+        w.usize(game.num_players);
+        w.usize(player);
+        send(&mut stream, &w)?;
+        println!("Player {} joined from {}", player + 1, addr);
+        clients.push(stream);
+    }
+
+    Ok(NetLink { player: 0, peers: Peers::Host(clients) })
+}
+
+/// Connects to a host and learns which game to build
+pub fn join(addr: impl ToSocketAddrs) -> io::Result<(NetLink, NetGame)> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(NET_TIMEOUT))?;
+
+    let bytes = recv(&mut stream)?;
+    let mut r = ByteReader::new(&bytes);
+    if r.take(NET_MAGIC.len())? != NET_MAGIC {
+        return Err(invalid_data("Not a snaek host"));
+    }
+    let version = r.u8()?;
+    if version != NET_VERSION {
+        return Err(invalid_data(&format!("Unsupported network version {}", version)));
+    }
+    let game = NetGame { seed: r.u64()?, level_index: r.usize()?, num_players: r.usize()? };
+    let player = r.usize()?;
+    if !(2..=MAX_PLAYERS).contains(&game.num_players) || player == 0 || player >= game.num_players {
+        return Err(invalid_data("Bad player count"));
+    }
+    if LEVELS.get(game.level_index).is_none() {
+        return Err(invalid_data("Unknown level"));
+    }
+    println!("Joined as player {} of {}", player + 1, game.num_players);
+
+    Ok((NetLink { player, peers: Peers::Client(stream) }, game))
+}
+
+/// Plays in lockstep with the other instances, stopping as soon as their boards differ
+pub struct NetController {
+    /// Where this instance's own actions come from
+    local: Box<dyn Controller>,
+    link: NetLink,
+    /// The frame number and board hash this tick's actions were gathered on
+    frame: (usize, u64),
+    /// This tick's actions that only apply here, held back until the exchange is done
+    held: Vec<UserAction>,
+}
+impl NetController {
+    pub fn new(local: Box<dyn Controller>, link: NetLink) -> NetController {
+        NetController { local, link, frame: (0, 0), held: Vec::new() }
+    }
+
+    /// Splits out the actions that only apply here, and points the rest at this instance's player
+    fn sort_local(&self, actions: Vec<UserAction>) -> (Vec<UserAction>, Vec<UserAction>) {
+        let player = self.link.player;
+        let (mut shared, mut local) = (Vec::new(), Vec::new());
+        for action in actions {
+            match action {
+                UserAction::Turn(_, dir) => shared.push(UserAction::Turn(player, dir)),
+                UserAction::Buy(_) => shared.push(UserAction::Buy(player)),
+                UserAction::Activate(_, kind) => shared.push(UserAction::Activate(player, kind)),
+                UserAction::RivalTurn(..) | UserAction::ShopItem(..) | UserAction::Restart => shared.push(action),
+                UserAction::Debug | UserAction::Save | UserAction::Quit => local.push(action),
+                UserAction::Load => println!("Loading is disabled in network games"),
+            }
+        }
+        (shared, local)
+    }
+
+    fn exchange(&mut self, frame_num: usize, hash: u64, shared: Vec<UserAction>) -> io::Result<Vec<UserAction>> {
+        match &mut self.link.peers {
+            Peers::Host(clients) => {
+                let mut all = shared;
+                for (i, stream) in clients.iter_mut().enumerate() {
+                    let (their_frame, their_hash, actions) = read_frame(&recv(stream)?)?;
+                    if their_frame != frame_num || their_hash != hash {
+                        return Err(desync(i + 1, frame_num, hash, their_frame, their_hash));
+                    }
+                    all.extend(actions);
+                }
+                let frame = write_frame(frame_num, hash, &all);
+                for stream in clients.iter_mut() {
+                    send(stream, &frame)?;
+                }
+                Ok(all)
+            }
+            Peers::Client(stream) => {
+                send(stream, &write_frame(frame_num, hash, &shared))?;
+                let (host_frame, host_hash, all) = read_frame(&recv(stream)?)?;
+                if host_frame != frame_num || host_hash != hash {
+                    return Err(desync(0, frame_num, hash, host_frame, host_hash));
+                }
+                Ok(all)
+            }
+        }
+    }
+}
+impl Controller for NetController {
+    fn actions(&mut self, s: &GameState) -> Option<Vec<UserAction>> {
+        let actions = self.local.actions(s)?;
+        let (shared, local) = self.sort_local(actions);
+        self.frame = (s.frame_num, board_hash(s));
+        self.held = local;
+        Some(shared)
+    }
+
+    fn sync(&mut self, shared: Vec<UserAction>) -> Option<Vec<UserAction>> {
+        let (frame_num, hash) = self.frame;
+        match self.exchange(frame_num, hash, shared) {
+            Ok(mut actions) => {
+                actions.append(&mut self.held);
+                Some(actions)
+            }
+            Err(err) => {
+                println!("Network game stopped: {}", err);
+                None
+            }
+        }
+    }
+}
+
+/// The hash compared between instances each tick
+fn board_hash(s: &GameState) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    s.board.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn desync(player: usize, frame_num: usize, hash: u64, their_frame: usize, their_hash: u64) -> io::Error {
+    invalid_data(&format!(
+        "Out of sync with player {}: frame {} hash {:016x} here, frame {} hash {:016x} there",
+        player + 1, frame_num, hash, their_frame, their_hash,
+    ))
+}
+
+fn write_frame(frame_num: usize, hash: u64, actions: &[UserAction]) -> ByteWriter {
+    let mut w = ByteWriter::new();
+    w.usize(frame_num);
+    w.u64(hash);
+    w.usize(actions.len());
+    for &action in actions {
+        let (tag, arg) = action_to_bytes(action);
+        w.u8(tag);
+        w.u64(arg);
+    }
+    w
+}
+
+fn read_frame(bytes: &[u8]) -> io::Result<(usize, u64, Vec<UserAction>)> {
+    let mut r = ByteReader::new(bytes);
+    let frame_num = r.usize()?;
+    let hash = r.u64()?;
+    let count = r.usize()?;
+    let mut actions = Vec::new();
+    for _ in 0..count {
+        actions.push(action_from_bytes(r.u8()?, r.u64()?)?);
+    }
+    Ok((frame_num, hash, actions))
+}
+
+/// Sends one message, prefixed with its length
+fn send(stream: &mut TcpStream, message: &ByteWriter) -> io::Result<()> {
+    let mut w = ByteWriter::new();
+    w.usize(message.bytes.len());
+    w.bytes(&message.bytes);
+    stream.write_all(&w.bytes)
+}
+
+fn recv(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut len = [0; 8];
+    stream.read_exact(&mut len)?;
+    let len = ByteReader::new(&len).usize()?;
+    if len > MAX_MESSAGE_LEN {
+        return Err(invalid_data("Message too long"));
+    }
+    let mut bytes = vec![0; len];
+    stream.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use super::super::{
+        controller::{BotController, RivalController},
+        logic::reset_with_seed,
+        test_util::{play_frames, VOLCANO},
+    };
+
+    /// Plays `frames` frames as this link's player, returning the board hash from the start of each
+    fn play(link: NetLink, game: &NetGame, frames: usize) -> Vec<u64> {
+        let (mut s, mut l) = reset_with_seed(game.seed, game.level_index, game.num_players);
+        let bot = BotController::new(link.player, game.seed);
+        let mut controllers: Vec<Box<dyn Controller>> = vec![
+            Box::new(NetController::new(Box::new(bot), link)),
+            Box::new(RivalController::new(game.seed)),
+        ];
+        let mut hashes = Vec::new();
+        play_frames(&mut s, &mut l, &mut controllers, frames, |s| hashes.push(board_hash(s)));
+        hashes
+    }
+
+    #[test]
+    fn instances_stay_in_lockstep_over_loopback() {
+        let game = NetGame { seed: 5, level_index: VOLCANO, num_players: 2 };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = thread::spawn(move || {
+            let (link, game) = join(addr).unwrap();
+            play(link, &game, 20)
+        });
+        let host_hashes = play(accept_players(listener, &game).unwrap(), &game, 20);
+        let client_hashes = client.join().unwrap();
+
+        assert_eq!(host_hashes, client_hashes);
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use super::types::{
+    Board,
+    CellFloor,
+    CellObject,
+    CellState,
+    Coord,
+    Dir,
+};
+
+/// What a route may cross and what it would rather avoid. Every step costs 1 on top of these
+#[derive(Clone, Copy, Default)]
+pub struct PathOptions {
+    /// Lava and walls can be crossed while invincible. The border never can
+    pub invincible: bool,
+    /// The way the snake is going. The route won't start by doubling back
+    pub facing: Option<Dir>,
+    /// Extra cost for each unit of water depth stepped into
+    pub water_cost: usize,
+    /// Extra cost for each unit of elevation climbed
+    pub climb_cost: usize,
+    /// Routes that would cost more than this aren't looked for
+    pub max_cost: Option<usize>,
+}
+
+/// Whether moving onto `cell` kills a snake
+pub fn is_lethal(cell: CellState, invincible: bool) -> bool {
+    match cell {
+        CellState { obj: CellObject::Border, .. } => true,
+        CellState { floor: CellFloor::Lava { .. }, .. } |
+        CellState { obj: CellObject::Wall, .. } => !invincible,
+        _ => false,
+    }
+}
+
+/// The moves along the cheapest route from `from` to any cell that `goal` accepts
+pub fn find_nearest(board: &Board, from: Coord, options: PathOptions, goal: impl Fn(Coord, CellState) -> bool) -> Option<Vec<Dir>> {
+    let mut costs = HashMap::from([(from, 0)]);
+    let mut came_from: HashMap<Coord, (Coord, Dir)> = HashMap::new();
+    let mut open = BinaryHeap::from([Reverse((0, from.x, from.y))]);
+
+    while let Some(Reverse((cost, x, y))) = open.pop() {
+        let coord = Coord { x, y };
+        if cost > costs[&coord] {
+            continue;
+        }
+        if goal(coord, board.cell_at(coord)) {
+            let mut dirs = Vec::new();
+            let mut at = coord;
+            while let Some(&(prev, dir)) = came_from.get(&at) {
+                dirs.push(dir);
+                at = prev;
+            }
+            dirs.reverse();
+            return Some(dirs);
+        }
+
+        for dir in Dir::ALL {
+            if coord == from && options.facing.map_or(false, |facing| dir.is_opposite(facing)) {
+                continue;
+            }
+            let next = coord.add_wrapped(dir);
+            let cell = board.cell_at(next);
+            if is_lethal(cell, options.invincible) {
+                continue;
+            }
+            let next_cost = cost + step_cost(board.cell_at(coord), cell, options);
+            if options.max_cost.map_or(false, |max_cost| next_cost > max_cost) {
+                continue;
+            }
+            if costs.get(&next).map_or(true, |&old| next_cost < old) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, (coord, dir));
+                open.push(Reverse((next_cost, next.x, next.y)));
+            }
+        }
+    }
+    None
+}
+
+fn step_cost(from: CellState, to: CellState, options: PathOptions) -> usize {
+    let depth = match to.floor {
+        CellFloor::Water { depth, .. } => depth as usize,
+        _ => 0,
+    };
+    let climb = to.elev.saturating_sub(from.elev) as usize;
+    1 + depth * options.water_cost + climb * options.climb_cost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{art::BoardArt, types::B_WIDTH};
+
+    fn to(goal: Coord) -> impl Fn(Coord, CellState) -> bool {
+        move |coord, _| coord == goal
+    }
+
+    #[test]
+    fn wraps_across_the_edge() {
+        let board = Board::new_filled(CellFloor::Empty);
+        let from = Coord { x: 0, y: 10 };
+        let dirs = find_nearest(&board, from, PathOptions::default(), to(Coord { x: B_WIDTH - 1, y: 10 }));
+        assert_eq!(dirs, Some(vec![Dir::Left]));
+    }
+
+    #[test]
+    fn goes_around_lethal_cells() {
+        let (from, goal) = (Coord { x: 10, y: 10 }, Coord { x: 12, y: 10 });
+        for block in [CellObject::Wall, CellObject::Border] {
+            let mut board = Board::new_filled(CellFloor::Empty);
+            board.pt((11, 10), block);
+            let dirs = find_nearest(&board, from, PathOptions::default(), to(goal)).unwrap();
+            assert_eq!(dirs.len(), 4);
+        }
+        let mut board = Board::new_filled(CellFloor::Empty);
+        board.pt((11, 10), CellFloor::Lava { depth: 1, heat: 0 });
+        let dirs = find_nearest(&board, from, PathOptions::default(), to(goal)).unwrap();
+        assert_eq!(dirs.len(), 4);
+    }
+
+    #[test]
+    fn crosses_lava_and_walls_when_invincible() {
+        let (from, goal) = (Coord { x: 10, y: 10 }, Coord { x: 13, y: 10 });
+        let mut board = Board::new_filled(CellFloor::Empty);
+        board.pt((11, 10), CellObject::Wall);
+        board.pt((12, 10), CellFloor::Lava { depth: 1, heat: 0 });
+        let options = PathOptions { invincible: true, ..PathOptions::default() };
+        let dirs = find_nearest(&board, from, options, to(goal));
+        assert_eq!(dirs, Some(vec![Dir::Right; 3]));
+
+        // Not even invincibility gets through the border
+        board.pt((11, 10), CellObject::Border);
+        let dirs = find_nearest(&board, from, options, to(goal)).unwrap();
+        assert_eq!(dirs.len(), 5);
+    }
+
+    #[test]
+    fn never_starts_by_reversing() {
+        let board = Board::new_filled(CellFloor::Empty);
+        let options = PathOptions { facing: Some(Dir::Right), ..PathOptions::default() };
+        let dirs = find_nearest(&board, Coord { x: 10, y: 10 }, options, to(Coord { x: 9, y: 10 })).unwrap();
+        assert_ne!(dirs[0], Dir::Left);
+        assert_eq!(dirs.len(), 3);
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    bytes::{ByteReader, ByteWriter, invalid_data},
+    levels::LEVELS,
+    logic::UserAction,
+    types::{Dir, PowerupType, NUM_SHOP_ITEMS},
+};
+
+/// The directory that new recordings are written to
+pub const REPLAY_DIR: &str = "replays";
+
+const REPLAY_MAGIC: &[u8; 4] = b"SNKR";
+const REPLAY_VERSION: u8 = 1;
+
+/// A recorded run: how it started and the actions applied on each frame
+pub struct Replay {
+    pub seed: u64,
+    pub level_index: usize,
+    pub num_players: usize,
+    pub actions: Vec<(usize, UserAction)>,
+}
+impl Replay {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Replay> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        let mut r = ByteReader::new(&bytes);
+
+        if r.take(REPLAY_MAGIC.len())? != REPLAY_MAGIC {
+            return Err(invalid_data("Not a replay file"));
+        }
+        let version = r.u8()?;
+        if version != REPLAY_VERSION {
+            return Err(invalid_data(&format!("Unsupported replay version {}", version)));
+        }
+        let seed = r.u64()?;
+        let level_index = r.usize()?;
+        if LEVELS.get(level_index).is_none() {
+            return Err(invalid_data("Unknown level"));
+        }
+        let num_players = r.usize()?;
+
+        let mut actions = Vec::new();
+        while !r.is_empty() {
+            let frame_num = r.usize()?;
+            let action = action_from_bytes(r.u8()?, r.u64()?)?;
+            actions.push((frame_num, action));
+        }
+
+        Ok(Replay { seed, level_index, num_players, actions })
+    }
+}
+
+/// Appends every applied `UserAction` to a replay file as it happens
+pub struct ReplayRecorder {
+    file: File,
+}
+impl ReplayRecorder {
+    pub fn create(path: impl AsRef<Path>, seed: u64, level_index: usize, num_players: usize) -> io::Result<ReplayRecorder> {
+        let mut file = File::create(path)?;
+        let mut header = ByteWriter::new();
+        header.bytes(REPLAY_MAGIC);
+        header.u8(REPLAY_VERSION);
+        header.u64(seed);
+        header.usize(level_index);
+        header.usize(num_players);
+        file.write_all(&header.bytes)?;
+        Ok(ReplayRecorder { file })
+    }
+
+    /// Creates a recorder in `REPLAY_DIR` named after the current time
+    pub fn create_in_replay_dir(seed: u64, level_index: usize, num_players: usize) -> io::Result<ReplayRecorder> {
+        fs::create_dir_all(REPLAY_DIR)?;
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = Path::new(REPLAY_DIR).join(format!("{}_{}.replay", secs, seed));
+        println!("Recording replay to {}", path.display());
+        Self::create(path, seed, level_index, num_players)
+    }
+
+    pub fn record(&mut self, frame_num: usize, action: UserAction) {
+        let (tag, arg) = action_to_bytes(action);
+        let mut record = ByteWriter::new();
+        record.usize(frame_num);
+        record.u8(tag);
+        record.u64(arg);
+        if let Err(err) = self.file.write_all(&record.bytes) {
+            println!("Unable to record action {:?}: {}", action, err);
+        }
+    }
+}
+
+/// The tag and argument an action is stored as, in replays and over the network
+pub fn action_to_bytes(action: UserAction) -> (u8, u64) {
+    match action {
+        UserAction::Turn(player, dir) => (0, player_arg(player, dir as u8)),
+        UserAction::RivalTurn(rival, dir) => (12, player_arg(rival, dir as u8)),
+        UserAction::ShopItem(item) => (4, item as u64),
+        UserAction::Buy(player) => (5, player as u64),
+        UserAction::Restart => (6, 0),
+        UserAction::Quit => (7, 0),
+        UserAction::Debug => (8, 0),
+        UserAction::Save => (9, 0),
+        UserAction::Load => (10, 0),
+        UserAction::Activate(player, kind) => (11, player_arg(player, kind as u8)),
+    }
+}
+
+pub fn action_from_bytes(tag: u8, arg: u64) -> io::Result<UserAction> {
+    Ok(match tag {
+        0 => UserAction::Turn((arg >> 8) as usize, dir_from_arg(arg)?),
+        4 if arg < NUM_SHOP_ITEMS as u64 => UserAction::ShopItem(arg as usize),
+        4 => return Err(invalid_data(&format!("Unknown shop item {}", arg))),
+        5 => UserAction::Buy(arg as usize),
+        6 => UserAction::Restart,
+        7 => UserAction::Quit,
+        8 => UserAction::Debug,
+        9 => UserAction::Save,
+        10 => UserAction::Load,
+        11 => match PowerupType::ALL.get((arg & 0xff) as usize) {
+            Some(&kind) => UserAction::Activate((arg >> 8) as usize, kind),
+            None => return Err(invalid_data(&format!("Unknown powerup {}", arg & 0xff))),
+        },
+        12 => UserAction::RivalTurn((arg >> 8) as usize, dir_from_arg(arg)?),
+        _ => return Err(invalid_data(&format!("Unknown action tag {}", tag))),
+    })
+}
+
+fn dir_from_arg(arg: u64) -> io::Result<Dir> {
+    match Dir::ALL.get((arg & 0xff) as usize) {
+        Some(&dir) => Ok(dir),
+        None => Err(invalid_data(&format!("Unknown direction {}", arg & 0xff))),
+    }
+}
+
+/// Packs a player index and a small value into one action argument
+fn player_arg(player: usize, value: u8) -> u64 {
+    (player as u64) << 8 | value as u64
+}
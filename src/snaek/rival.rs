@@ -0,0 +1,121 @@
+use std::collections::{HashSet, VecDeque};
+
+use rand::Rng;
+
+use super::{
+    path::{find_nearest, is_lethal, PathOptions},
+    types::{
+        Board,
+        CellObject,
+        CellState,
+        Coord,
+        Dir,
+        GameState,
+        Player,
+        Rival,
+        Snake,
+        START_SNAKE_LEN,
+    },
+};
+
+/// How many steps ahead a rival looks for food and open space
+const RIVAL_SIGHT: usize = 30;
+/// A rival won't head into space smaller than this many cells if it has a choice
+const RIVAL_MIN_ROOM: usize = 40;
+/// The chance (1 in this many) that a rival with nothing better to do turns anyway
+const RIVAL_WANDER_ONE_IN: u32 = 20;
+/// Rivals don't start any closer than this to where a player starts
+const RIVAL_SPAWN_DIST: usize = 30;
+const RIVAL_SPAWN_TRIES: usize = 100;
+
+/// Replaces the rivals with the level's starting set, placed at random on safe ground away from the players
+pub fn spawn_rivals(s: &mut GameState) {
+    s.rivals.clear();
+    for _ in 0..s.level.num_rivals {
+        let start = (0..RIVAL_SPAWN_TRIES)
+            .map(|_| s.rng.gen::<Coord>())
+            .find(|&coord| {
+                is_safe(s.board.cell_at(coord))
+                    && (0..s.players.len()).all(|i| distance(coord, Player::start(i)) >= RIVAL_SPAWN_DIST)
+                    && !s.players.iter().any(|p| p.snake.covers(coord))
+            });
+        match start {
+            Some(start) => s.rivals.push(Rival {
+                snake: Snake::new(start, s.rng.gen(), START_SNAKE_LEN),
+                coins: 0,
+                coin_progress: 0,
+            }),
+            None => println!("Unable to find somewhere safe for a rival to start"),
+        }
+    }
+}
+
+/// Where a computer-controlled snake should turn to reach food safely, if anywhere
+pub fn choose_dir(board: &Board, snake: &Snake, rng: &mut impl Rng) -> Option<Dir> {
+    let ahead = snake.dir();
+    let room = look_around(board, snake);
+    let food = find_nearest(
+        board,
+        snake.head_pos(),
+        PathOptions { facing: Some(ahead), max_cost: Some(RIVAL_SIGHT), ..PathOptions::default() },
+        |_, cell| matches!(cell.obj, CellObject::Food(..)),
+    ).and_then(|dirs| dirs.first().copied());
+    let roomy = |dir: Dir| room[dir as usize].map_or(false, |room| room >= RIVAL_MIN_ROOM);
+
+    if let Some(dir) = food.filter(|&dir| roomy(dir)) {
+        return Some(dir);
+    }
+    if roomy(ahead) && !rng.gen_ratio(1, RIVAL_WANDER_ONE_IN) {
+        return None;
+    }
+    // Otherwise turn toward the most room, picking at random between equally good ways
+    let most_room = Dir::ALL
+        .into_iter()
+        .filter(|&dir| dir != ahead || !roomy(ahead))
+        .filter_map(|dir| room[dir as usize])
+        .max()?;
+    let best: Vec<Dir> = Dir::ALL
+        .into_iter()
+        .filter(|&dir| (dir != ahead || !roomy(ahead)) && room[dir as usize] == Some(most_room))
+        .collect();
+    Some(best[rng.gen_range(0..best.len())])
+}
+
+/// How many safe cells the snake can reach by first moving each way, indexed by `Dir`
+fn look_around(board: &Board, snake: &Snake) -> [Option<usize>; 4] {
+    let head = snake.head_pos();
+    let mut room = [None; 4];
+    let mut seen = HashSet::from([head]);
+    let mut queue = VecDeque::new();
+
+    for dir in Dir::ALL {
+        let next = head.add_wrapped(dir);
+        if !dir.is_opposite(snake.dir()) && is_safe(board.cell_at(next)) && seen.insert(next) {
+            room[dir as usize] = Some(0);
+            queue.push_back((next, dir, 1));
+        }
+    }
+
+    while let Some((coord, first, dist)) = queue.pop_front() {
+        room[first as usize] = room[first as usize].map(|room| room + 1);
+        if dist == RIVAL_SIGHT {
+            continue;
+        }
+        for dir in Dir::ALL {
+            let next = coord.add_wrapped(dir);
+            if is_safe(board.cell_at(next)) && seen.insert(next) {
+                queue.push_back((next, first, dist + 1));
+            }
+        }
+    }
+
+    room
+}
+
+fn is_safe(cell: CellState) -> bool {
+    !is_lethal(cell, false)
+}
+
+fn distance(a: Coord, b: Coord) -> usize {
+    a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+}
@@ -0,0 +1,399 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use rand::SeedableRng;
+
+use super::{
+    bytes::{ByteReader, ByteWriter, invalid_data},
+    levels::{LEVELS, LevelState},
+    scoreboard::SCORE_BANNER_VERT,
+    types::{
+        Board,
+        CellFloor,
+        CellObject,
+        CellState,
+        Coord,
+        DebugInfo,
+        Dir,
+        FailReason,
+        GameRng,
+        GameState,
+        IndicatorType,
+        Player,
+        Rival,
+        PowerupType,
+        ShopItem,
+        ShopState,
+        Snake,
+        SnakePart,
+        SnakeColor,
+        MAX_PLAYERS,
+        NUM_SHOP_ITEMS,
+        NUM_POWERUP_TYPES,
+    },
+};
+
+/// The directory that quicksaves are written to
+pub const SAVE_DIR: &str = "saves";
+pub const QUICKSAVE_FILE: &str = "quicksave.sav";
+
+const SAVE_MAGIC: &[u8; 4] = b"SNKS";
+const SAVE_VERSION: u8 = 1;
+
+pub fn quicksave_path() -> std::path::PathBuf {
+    Path::new(SAVE_DIR).join(QUICKSAVE_FILE)
+}
+
+/// Writes the game in progress, including the level's own state, to `path`
+pub fn save_game(path: impl AsRef<Path>, s: &GameState, l: &dyn LevelState) -> io::Result<()> {
+    if let Some(dir) = path.as_ref().parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut w = ByteWriter::new();
+    w.bytes(SAVE_MAGIC);
+    w.u8(SAVE_VERSION);
+
+    w.usize(s.level.index);
+    for cell in s.board.cells() {
+        write_cell(&mut w, cell);
+    }
+
+    w.usize(s.players.len());
+    for player in &s.players {
+        write_player(&mut w, player);
+    }
+    w.usize(s.rivals.len());
+    for rival in &s.rivals {
+        write_snake(&mut w, &rival.snake);
+        w.usize(rival.coins);
+        w.usize(rival.coin_progress);
+    }
+
+    for item in &s.shop.powerups {
+        w.u8(item.kind as u8);
+        w.usize(item.price);
+    }
+    w.usize(s.shop.selected);
+    w.usize(s.shop.price_multiplier);
+
+    w.usize(s.price_multiplier);
+    w.bool(s.failed);
+    w.u8(match s.fail_reason {
+        None => 0,
+        Some(FailReason::HitWallOrLava) => 1,
+        Some(FailReason::HitBorder) => 2,
+        Some(FailReason::LastSeedDied) => 3,
+    });
+    w.usize(s.seed_count);
+    w.usize(s.level_time);
+    w.bool(s.final_score.is_some());
+    w.usize(s.final_score.unwrap_or(0));
+    w.usize(s.frame_num);
+
+    w.u64(s.seed);
+    w.u32(s.salt);
+    w.bytes(&s.rng.get_seed());
+    w.u64(s.rng.get_stream());
+    w.u128(s.rng.get_word_pos());
+
+    l.save(&mut w);
+
+    File::create(path)?.write_all(&w.bytes)
+}
+
+/// Restores a game written by `save_game`
+pub fn load_game(path: impl AsRef<Path>) -> io::Result<(GameState, Box<dyn LevelState>)> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    let mut r = ByteReader::new(&bytes);
+
+    if r.take(SAVE_MAGIC.len())? != SAVE_MAGIC {
+        return Err(invalid_data("Not a save file"));
+    }
+    let version = r.u8()?;
+    if version != SAVE_VERSION {
+        return Err(invalid_data(&format!("Unsupported save version {}", version)));
+    }
+
+    let level_index = r.usize()?;
+    let level = *LEVELS.get(level_index).ok_or_else(|| invalid_data("Unknown level"))?;
+    let mut board = Board::new_filled(CellFloor::Empty);
+    for cell in board.cells_mut() {
+        *cell = read_cell(&mut r)?;
+    }
+
+    let num_players = r.usize()?;
+    if !(1..=MAX_PLAYERS).contains(&num_players) {
+        return Err(invalid_data("Bad player count"));
+    }
+    let mut players = Vec::with_capacity(num_players);
+    for _ in 0..num_players {
+        players.push(read_player(&mut r)?);
+    }
+    let num_rivals = r.usize()?;
+    let mut rivals = Vec::new();
+    for _ in 0..num_rivals {
+        rivals.push(Rival { snake: read_snake(&mut r)?, coins: r.usize()?, coin_progress: r.usize()? });
+    }
+
+    let mut powerups = [ShopItem { kind: PowerupType::Water, price: 0 }; NUM_SHOP_ITEMS];
+    for item in &mut powerups {
+        *item = ShopItem { kind: read_powerup(&mut r)?, price: r.usize()? };
+    }
+    let shop = ShopState {
+        powerups,
+        selected: r.usize()?.min(NUM_SHOP_ITEMS - 1),
+        price_multiplier: r.usize()?,
+    };
+
+    let price_multiplier = r.usize()?;
+    let failed = r.bool()?;
+    let fail_reason = match r.u8()? {
+        0 => None,
+        1 => Some(FailReason::HitWallOrLava),
+        2 => Some(FailReason::HitBorder),
+        3 => Some(FailReason::LastSeedDied),
+        _ => return Err(invalid_data("Unknown fail reason")),
+    };
+    let seed_count = r.usize()?;
+    let level_time = r.usize()?;
+    let has_final_score = r.bool()?;
+    let final_score = Some(r.usize()?).filter(|_| has_final_score);
+    let frame_num = r.usize()?;
+
+    let seed = r.u64()?;
+    let salt = r.u32()?;
+    let mut rng = GameRng::from_seed(r.array()?);
+    rng.set_stream(r.u64()?);
+    rng.set_word_pos(r.u128()?);
+
+    // Whatever the fresh level state picked at random is replaced by `load`
+    let mut l = (level.new_level_state)(&mut GameRng::seed_from_u64(seed));
+    l.load(&mut r)?;
+
+    let mut s = GameState {
+        level,
+        board,
+        shop,
+        players,
+        rivals,
+        price_multiplier,
+        failed,
+        fail_reason,
+        seed_count,
+        level_time,
+        final_score,
+        frame_num,
+        debug_screen: false,
+        debug_info: DebugInfo::default(),
+        seed,
+        rng,
+        salt,
+    };
+    s.redraw_scoreboards();
+
+    Ok((s, l))
+}
+
+fn write_player(w: &mut ByteWriter, player: &Player) {
+    write_snake(w, &player.snake);
+    w.usize(player.coins);
+    w.usize(player.coin_progress);
+    w.usize(player.invinc_time);
+    w.usize(player.dirt);
+    for &count in &player.inventory {
+        w.usize(count);
+    }
+}
+
+fn read_player(r: &mut ByteReader) -> io::Result<Player> {
+    let snake = read_snake(r)?;
+    let coins = r.usize()?;
+    let coin_progress = r.usize()?;
+    let invinc_time = r.usize()?;
+    let dirt = r.usize()?;
+    let mut inventory = [0; NUM_POWERUP_TYPES];
+    for count in &mut inventory {
+        *count = r.usize()?;
+    }
+    Ok(Player {
+        snake,
+        scoreboard: Board::from_bytes(SCORE_BANNER_VERT),
+        coins,
+        coin_progress,
+        invinc_time,
+        dirt,
+        inventory,
+    })
+}
+
+fn write_snake(w: &mut ByteWriter, snake: &Snake) {
+    w.u8(snake.dir() as u8);
+    w.usize(snake.len());
+    w.usize(snake.body().count());
+    for Coord { x, y } in snake.body() {
+        w.usize(x);
+        w.usize(y);
+    }
+}
+
+fn read_snake(r: &mut ByteReader) -> io::Result<Snake> {
+    let dir = read_dir(r)?;
+    let len = r.usize()?;
+    let body_len = r.usize()?;
+    if body_len == 0 || body_len > len.max(1) {
+        return Err(invalid_data("Bad snake length"));
+    }
+    let mut body = VecDeque::with_capacity(body_len);
+    for _ in 0..body_len {
+        let segment = Coord { x: r.usize()?, y: r.usize()? };
+        if !segment.in_bounds() {
+            return Err(invalid_data("Snake is off the board"));
+        }
+        body.push_back(segment);
+    }
+    Ok(Snake::with_body(body, dir, len))
+}
+
+fn write_cell(w: &mut ByteWriter, cell: &CellState) {
+    match cell.floor {
+        CellFloor::Empty => w.u8(0),
+        CellFloor::Water { depth, sediment } => { w.u8(1); w.u8(depth); w.u8(sediment); }
+        CellFloor::Lava { depth, heat } => { w.u8(2); w.u8(depth); w.u8(heat); }
+        CellFloor::Seed { height, saturation } => { w.u8(3); w.u8(height); w.i8(saturation); }
+        CellFloor::Indicator(indicator) => { w.u8(4); write_indicator(w, indicator); }
+        CellFloor::Fire { fuel } => { w.u8(5); w.u8(fuel); }
+    }
+    match cell.obj {
+        CellObject::None => w.u8(0),
+        CellObject::Wall => w.u8(1),
+        CellObject::Snake(color, part) => { w.u8(2); w.u8(color as u8); write_part_dir(w, part.front); write_part_dir(w, part.back); }
+        CellObject::Food(life) => { w.u8(3); w.usize(life); }
+        CellObject::Border => w.u8(4),
+    }
+    w.u8(cell.elev);
+    w.i8(cell.fertility);
+}
+
+fn read_cell(r: &mut ByteReader) -> io::Result<CellState> {
+    let floor = match r.u8()? {
+        0 => CellFloor::Empty,
+        1 => CellFloor::Water { depth: r.u8()?, sediment: r.u8()? },
+        2 => CellFloor::Lava { depth: r.u8()?, heat: r.u8()? },
+        3 => CellFloor::Seed { height: r.u8()?, saturation: r.i8()? },
+        4 => CellFloor::Indicator(read_indicator(r)?),
+        5 => CellFloor::Fire { fuel: r.u8()? },
+        _ => return Err(invalid_data("Unknown cell floor")),
+    };
+    let obj = match r.u8()? {
+        0 => CellObject::None,
+        1 => CellObject::Wall,
+        2 => {
+            let color = match r.u8()? {
+                0 => SnakeColor::DarkRed,
+                1 => SnakeColor::LightRed,
+                2 => SnakeColor::Head,
+                3 => SnakeColor::DarkBlue,
+                4 => SnakeColor::LightBlue,
+                5 => SnakeColor::BlueHead,
+                6 => SnakeColor::RivalDark,
+                7 => SnakeColor::RivalLight,
+                8 => SnakeColor::RivalHead,
+                _ => return Err(invalid_data("Unknown snake color")),
+            };
+            CellObject::Snake(color, SnakePart { front: read_part_dir(r)?, back: read_part_dir(r)? })
+        }
+        3 => CellObject::Food(r.usize()?),
+        4 => CellObject::Border,
+        _ => return Err(invalid_data("Unknown cell object")),
+    };
+    Ok(CellState { floor, obj, elev: r.u8()?, fertility: r.i8()? })
+}
+
+fn write_indicator(w: &mut ByteWriter, indicator: IndicatorType) {
+    match indicator {
+        IndicatorType::Empty => w.u8(0),
+        IndicatorType::MSPTNormal => w.u8(1),
+        IndicatorType::MSPTOver => w.u8(2),
+        IndicatorType::Coin => w.u8(3),
+        IndicatorType::PM => w.u8(4),
+        IndicatorType::Powerup(kind) => { w.u8(5); w.u8(kind as u8); }
+        IndicatorType::Text => w.u8(6),
+    }
+}
+
+fn read_indicator(r: &mut ByteReader) -> io::Result<IndicatorType> {
+    Ok(match r.u8()? {
+        0 => IndicatorType::Empty,
+        1 => IndicatorType::MSPTNormal,
+        2 => IndicatorType::MSPTOver,
+        3 => IndicatorType::Coin,
+        4 => IndicatorType::PM,
+        5 => IndicatorType::Powerup(read_powerup(r)?),
+        6 => IndicatorType::Text,
+        _ => return Err(invalid_data("Unknown indicator")),
+    })
+}
+
+fn read_powerup(r: &mut ByteReader) -> io::Result<PowerupType> {
+    Ok(match r.u8()? {
+        0 => PowerupType::Water,
+        1 => PowerupType::Explosive,
+        2 => PowerupType::Shovel,
+        3 => PowerupType::Seed,
+        4 => PowerupType::Invincibility,
+        _ => return Err(invalid_data("Unknown powerup")),
+    })
+}
+
+/// Where a snake part doesn't join on to anything, it is written as 4
+fn write_part_dir(w: &mut ByteWriter, dir: Option<Dir>) {
+    w.u8(dir.map_or(4, |dir| dir as u8));
+}
+
+fn read_part_dir(r: &mut ByteReader) -> io::Result<Option<Dir>> {
+    Ok(match r.u8()? {
+        4 => None,
+        dir => Some(*Dir::ALL.get(dir as usize).ok_or_else(|| invalid_data("Unknown direction"))?),
+    })
+}
+
+fn read_dir(r: &mut ByteReader) -> io::Result<Dir> {
+    Ok(match r.u8()? {
+        0 => Dir::Up,
+        1 => Dir::Left,
+        2 => Dir::Down,
+        3 => Dir::Right,
+        _ => return Err(invalid_data("Unknown direction")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        logic::reset_with_seed,
+        test_util::{play_frames, VOLCANO},
+    };
+
+    #[test]
+    fn loading_a_save_gives_back_the_same_game() {
+        let (mut s, mut l) = reset_with_seed(9, VOLCANO, 2);
+        play_frames(&mut s, &mut l, &mut [], 50, |_| {});
+
+        let dir = std::env::temp_dir().join(format!("snaek-save-test-{}", std::process::id()));
+        let (first, second) = (dir.join("first.sav"), dir.join("second.sav"));
+        save_game(&first, &s, &*l).unwrap();
+        let (loaded_s, loaded_l) = load_game(&first).unwrap();
+        save_game(&second, &loaded_s, &*loaded_l).unwrap();
+        let (first_bytes, second_bytes) = (fs::read(&first).unwrap(), fs::read(&second).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(first_bytes == second_bytes);
+    }
+}
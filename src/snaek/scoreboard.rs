@@ -7,6 +7,7 @@ use super::{
         ShopState,
         ShopItem,
         NUM_SHOP_ITEMS,
+        NUM_POWERUP_TYPES,
         Board,
         SB_WIDTH,
         SB_HEIGHT,
@@ -34,6 +35,14 @@ const PM_X: usize = SHOP_X + 6;
 const PM_Y: usize = SHOP_Y + NUM_SHOP_ITEMS * SHOP_ITEM_H + 2;
 const COINS_X: usize = SHOP_X + 6;
 const COINS_Y: usize = PM_Y + 7;
+const TIME_Y: usize = COINS_Y + 7;
+const SCORE_Y: usize = TIME_Y + 7;
+const DIRT_Y: usize = SCORE_Y + 7;
+const INVENTORY_X: usize = 1;
+const INVENTORY_Y: usize = DIRT_Y + 7;
+const INVENTORY_COLS: usize = 3;
+const INVENTORY_SLOT_W: usize = 9;
+const INVENTORY_SLOT_H: usize = 7;
 pub trait ScoreboardArt: BoardArt {
     fn shop(&mut self, shop: &ShopState) {
         for item_num in 0..NUM_SHOP_ITEMS {
@@ -45,12 +54,12 @@ pub trait ScoreboardArt: BoardArt {
             self.shop_item_display(shop, item_num, ShopItemFill::Remove);
         }
     }
-    fn pm(&mut self, shop: &ShopState) {
-        let pm = format!("{}", shop.price_multiplier);
+    fn pm(&mut self, price_multiplier: usize) {
+        let pm = format!("{}", price_multiplier);
         self.text(&pm, (PM_X, PM_Y), CellObject::None, ());
     }
-    fn pm_remove(&mut self, shop: &ShopState) {
-        let pm = format!("{}", shop.price_multiplier);
+    fn pm_remove(&mut self, price_multiplier: usize) {
+        let pm = format!("{}", price_multiplier);
         self.text(&pm, (PM_X, PM_Y), CellObject::Wall, ());
     }
     fn coins(&mut self, coins: usize) {
@@ -61,8 +70,49 @@ pub trait ScoreboardArt: BoardArt {
         let coins = format!("{}", coins);
         self.text(&coins, (COINS_X, COINS_Y), CellObject::Wall, ());
     }
+    fn time(&mut self, secs: usize) {
+        let time = format!("t {}", secs);
+        self.text(&time, (SHOP_X, TIME_Y), CellObject::None, ());
+    }
+    fn time_remove(&mut self, secs: usize) {
+        let time = format!("t {}", secs);
+        self.text(&time, (SHOP_X, TIME_Y), CellObject::Wall, ());
+    }
+    fn score(&mut self, score: usize) {
+        let score = format!("s {}", score);
+        self.text(&score, (SHOP_X, SCORE_Y), CellObject::None, ());
+    }
+    fn dirt(&mut self, dirt: usize) {
+        let dirt = format!("d {}", dirt);
+        self.text(&dirt, (SHOP_X, DIRT_Y), CellObject::None, ());
+    }
+    fn dirt_remove(&mut self, dirt: usize) {
+        let dirt = format!("d {}", dirt);
+        self.text(&dirt, (SHOP_X, DIRT_Y), CellObject::Wall, ());
+    }
+    fn inventory(&mut self, inventory: &[usize; NUM_POWERUP_TYPES]) {
+        for (kind, &count) in PowerupType::ALL.into_iter().zip(inventory) {
+            self.inventory_slot_display(kind, count, (kind, CellObject::None), CellObject::None);
+        }
+    }
+    fn inventory_remove(&mut self, inventory: &[usize; NUM_POWERUP_TYPES]) {
+        for (kind, &count) in PowerupType::ALL.into_iter().zip(inventory) {
+            self.inventory_slot_display(kind, count, (CellFloor::Empty, CellObject::Wall), CellObject::Wall);
+        }
+    }
+    fn inventory_slot_display(&mut self, kind: PowerupType, count: usize, icon_fill: impl Fill, count_fill: impl Fill) {
+        let slot = kind as usize;
+        let x = INVENTORY_X + INVENTORY_SLOT_W * (slot % INVENTORY_COLS);
+        let y = INVENTORY_Y + INVENTORY_SLOT_H * (slot / INVENTORY_COLS);
+
+        write_letter(&POWERUP_GRIDS[slot], x, y, self, icon_fill, ());
+
+        // Only one digit fits next to the icon
+        let count = format!("{}", count.min(9));
+        self.text(&count, (x + P_WIDTH + 1, y), count_fill, ());
+    }
     fn shop_item_display(&mut self, shop: &ShopState, item_num: usize, fill: ShopItemFill) {
-        if item_num > NUM_SHOP_ITEMS {
+        if item_num >= NUM_SHOP_ITEMS {
             return;
         }
 
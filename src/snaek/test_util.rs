@@ -0,0 +1,29 @@
+use scoped_threadpool::Pool;
+
+use super::{
+    controller::Controller,
+    levels::LevelState,
+    logic::{advance_board, handle_actions, NUM_BOARD_ADVANCE_THREADS},
+    types::GameState,
+};
+
+/// Indices into `LEVELS`
+pub const TUTORIAL: usize = 0;
+pub const VOLCANO: usize = 1;
+
+/// Lets the controllers act and advances the board, `frames` times. `each_frame` sees the game at the start of every frame
+pub fn play_frames(
+    s: &mut GameState,
+    l: &mut Box<dyn LevelState>,
+    controllers: &mut [Box<dyn Controller>],
+    frames: usize,
+    mut each_frame: impl FnMut(&GameState),
+) {
+    let mut pool = Pool::new(NUM_BOARD_ADVANCE_THREADS);
+    let mut turns = Default::default();
+    for _ in 0..frames {
+        each_frame(s);
+        assert!(!handle_actions(controllers, s, l, &mut turns, &mut None), "A controller stopped the game");
+        advance_board(s, &mut **l, &mut pool);
+    }
+}
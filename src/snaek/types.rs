@@ -1,27 +1,32 @@
 
-use std::{ops::{Range, Deref, DerefMut, Add}, mem::MaybeUninit};
+use std::{ops::{Range, Deref, DerefMut, Add}, mem::MaybeUninit, collections::VecDeque};
 
 use rand::{Rng, distributions::{Distribution, Standard}};
+use rand_chacha::ChaCha12Rng;
 
 use crate::snaek::levels::LEVELS;
 
-use super::{levels::{Level, LevelState}, art::Fill};
+use super::{levels::{Level, LevelState}, art::Fill, rival::spawn_rivals, scoreboard::{ScoreboardArt, SCORE_BANNER_VERT}};
 
 #[derive(Clone, Copy, Hash, PartialEq, Default, Debug)]
 pub enum CellFloor {
     #[default]
     Empty,
-    Water { depth: u8 },
-    Lava { depth: u8 },
+    /// `sediment` is eroded terrain carried by the water, deposited back into `elev` when it settles
+    Water { depth: u8, sediment: u8 },
+    /// `heat` counts down while the lava sits still. When it runs out, a layer cools into `elev`
+    Lava { depth: u8, heat: u8 },
     Seed { height: u8, saturation: i8 },
+    /// A burning seed. `fuel` is the height of the plant left to burn
+    Fire { fuel: u8 },
     Indicator(IndicatorType),
 }
 impl CellFloor {
     #[inline(always)]
     pub fn height(&self) -> u8 {
         match self {
-            CellFloor::Empty | CellFloor::Indicator(..) => 0,
-            CellFloor::Water { depth: height } | CellFloor::Lava { depth: height } | CellFloor::Seed { height, .. } => *height,
+            CellFloor::Empty | CellFloor::Fire { .. } | CellFloor::Indicator(..) => 0,
+            CellFloor::Water { depth: height, .. } | CellFloor::Lava { depth: height, .. } | CellFloor::Seed { height, .. } => *height,
         }
     }
 }
@@ -31,7 +36,7 @@ pub enum CellObject {
     #[default]
     None,
     Wall,
-    Snake(SnakeColor, usize),
+    Snake(SnakeColor, SnakePart),
     Food(usize),
     Border,
 }
@@ -44,6 +49,16 @@ pub enum PowerupType {
     Seed,
     Invincibility,
 }
+impl PowerupType {
+    pub const ALL: [PowerupType; NUM_POWERUP_TYPES] = [
+        PowerupType::Water,
+        PowerupType::Explosive,
+        PowerupType::Shovel,
+        PowerupType::Seed,
+        PowerupType::Invincibility,
+    ];
+}
+pub const NUM_POWERUP_TYPES: usize = 5;
 impl Distribution<PowerupType> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> PowerupType {
         match rng.gen_range(0..5) {
@@ -64,6 +79,15 @@ pub enum IndicatorType {
     Coin,
     PM,
     Powerup(PowerupType),
+    /// Instructions written on the board
+    Text,
+}
+
+/// Which ways a cell of snake joins on to the rest of it. The head's front is the way it is going
+#[derive(Clone, Copy, Hash, PartialEq, Debug)]
+pub struct SnakePart {
+    pub front: Option<Dir>,
+    pub back: Option<Dir>,
 }
 
 #[derive(Clone, Copy, Hash, PartialEq, Debug)]
@@ -71,6 +95,12 @@ pub enum SnakeColor {
     DarkRed,
     LightRed,
     Head,
+    DarkBlue,
+    LightBlue,
+    BlueHead,
+    RivalDark,
+    RivalLight,
+    RivalHead,
 }
 
 // Max seed height = fertility + saturation
@@ -78,6 +108,11 @@ pub const MAX_FERTILITY: i8 = 15;
 pub const MAX_SEED_HEIGHT: i8 = 30;
 pub const MAX_SATURATION: i8 = 15;
 pub const MIN_SATURATION_FOR_SEED_SPREAD: i8 = 4;
+/// Seeds at least this tall grow food and earn coins
+pub const MATURE_SEED_HEIGHT: u8 = 5;
+
+/// The number of ticks lava has to sit still before a layer of it cools into rock
+pub const LAVA_MAX_HEAT: u8 = 50;
 
 #[derive(Clone, Copy, Hash, PartialEq, Default, Debug)]
 pub struct CellState {
@@ -91,6 +126,11 @@ impl CellState {
     pub fn roof(&self) -> u8 {
         self.elev.saturating_add(self.floor.height())
     }
+    /// Changes fertility by `amount`, keeping it within `0..=MAX_FERTILITY`
+    #[inline(always)]
+    pub fn add_fertility(&mut self, amount: i8) {
+        self.fertility = self.fertility.saturating_add(amount).clamp(0, MAX_FERTILITY);
+    }
 }
 
 /// The width of the board in cells. Must be less than `isize::MAX`
@@ -158,8 +198,8 @@ impl<const W: usize, const H: usize> Board<W, H> {
             if y < H {
                 board_vec[y][x] = match floor {
                     0x0 => CellState { floor: CellFloor::Empty, obj: CellObject::None, elev, fertility },
-                    0x1 => CellState { floor: CellFloor::Water { depth: 1 }, obj: CellObject::None, elev, fertility },
-                    0x2 => CellState { floor: CellFloor::Lava { depth: 1 }, obj: CellObject::None, elev, fertility },
+                    0x1 => CellState { floor: CellFloor::Water { depth: 1, sediment: 0 }, obj: CellObject::None, elev, fertility },
+                    0x2 => CellState { floor: CellFloor::Lava { depth: 1, heat: LAVA_MAX_HEAT }, obj: CellObject::None, elev, fertility },
                     0x3 => CellState { floor: CellFloor::Empty, obj: CellObject::Wall, elev, fertility },
                     0x4 => CellState { floor: CellFloor::Empty, obj: CellObject::Border, elev, fertility },
                     0x5 => CellState { floor: CellFloor::Seed { height: 1, saturation: MAX_SATURATION }, obj: CellObject::None, elev, fertility },
@@ -296,22 +336,22 @@ pub mod board_ops {
 }
 
 
-#[derive(Clone, Copy, Hash, PartialEq, Debug)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub struct Coord {
     pub x: usize,
     pub y: usize,
 }
 impl Coord {
-    pub fn rand() -> Coord {
+    pub fn rand(rng: &mut impl Rng) -> Coord {
         Coord {
-            x: rand::thread_rng().gen_range(0..B_WIDTH),
-            y: rand::thread_rng().gen_range(0..B_HEIGHT),
+            x: rng.gen_range(0..B_WIDTH),
+            y: rng.gen_range(0..B_HEIGHT),
         }
     }
-    pub fn rand_range(x_range: Range<usize>, y_range: Range<usize>) -> Coord {
+    pub fn rand_range(rng: &mut impl Rng, x_range: Range<usize>, y_range: Range<usize>) -> Coord {
         Coord {
-            x: rand::thread_rng().gen_range(x_range),
-            y: rand::thread_rng().gen_range(y_range),
+            x: rng.gen_range(x_range),
+            y: rng.gen_range(y_range),
         }
     }
     pub fn add_checked(self, rhs: Dir) -> Option<Coord> {
@@ -346,6 +386,10 @@ impl Coord {
         let new_y = if new_y < 0 { B_HEIGHT - 1 } else if new_y as usize >= B_HEIGHT { 0 } else { new_y as usize };
         Coord { x: new_x, y: new_y }
     }
+    /// The way to step to reach `other`, if it is next door
+    pub fn dir_to(self, other: Coord) -> Option<Dir> {
+        Dir::ALL.into_iter().find(|&dir| self.add_wrapped(dir) == other)
+    }
     pub fn sub_wrapped(self, rhs: Dir) -> Coord {
         let (dx, dy) = rhs.get_diff();
         let (new_x, new_y) = (self.x as isize - dx, self.y as isize - dy);
@@ -388,12 +432,17 @@ pub enum Dir {
     Up, Left, Down, Right
 }
 impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::Up, Dir::Left, Dir::Down, Dir::Right];
+
     fn get_diff(&self) -> (isize, isize) {
         [(0, -1), (-1, 0), (0, 1), (1, 0)][*self as usize]
     }
-    fn is_opposite(&self, other: Dir) -> bool {
+    pub fn is_opposite(&self, other: Dir) -> bool {
         (*self as usize).abs_diff(other as usize) == 2
     }
+    pub fn opposite(self) -> Dir {
+        Dir::ALL[(self as usize + 2) % 4]
+    }
 }
 impl Distribution<Dir> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Dir {
@@ -408,56 +457,185 @@ impl Distribution<Dir> for Standard {
 
 #[derive(Debug)]
 pub struct Snake {
-    head: Coord,
+    /// Every cell the snake covers, head first
+    body: VecDeque<Coord>,
     dir: Dir,
+    /// How long the body grows to. A new snake starts as just its head and uncoils as it moves
     len: usize,
 }
 impl Snake {
     pub fn new(head: impl Into<Coord>, dir: Dir, len: usize) -> Snake {
-        Snake {
-            head: head.into(), dir, len
+        Snake::with_body(VecDeque::from([head.into()]), dir, len)
+    }
+    /// `body` is head first and must not be empty
+    pub fn with_body(body: VecDeque<Coord>, dir: Dir, len: usize) -> Snake {
+        assert!(!body.is_empty(), "A snake needs a head");
+        Snake { body, dir, len: len.max(1) }
+    }
+    /// A snake with its body laid out behind the head, folding back where a cell isn't `free`
+    pub fn laid_out(head: Coord, dir: Dir, len: usize, free: impl Fn(Coord) -> bool) -> Snake {
+        let side = if matches!(dir, Dir::Left | Dir::Right) { Dir::Down } else { Dir::Right };
+        let mut body = VecDeque::from([head]);
+        let (mut at, mut along) = (head, dir.opposite());
+        while body.len() < len {
+            let next = [at.add_wrapped(along), at.add_wrapped(side)]
+                .into_iter()
+                .find(|&next| free(next) && !body.contains(&next));
+            let Some(next) = next else { break };
+            if next == at.add_wrapped(side) {
+                along = along.opposite();
+            }
+            body.push_back(next);
+            at = next;
+        }
+        Snake::with_body(body, dir, len)
+    }
+    /// Moves the head forward, returning the cell the tail left, if it left one
+    pub fn advance(&mut self) -> Option<Coord> {
+        let head = self.head_pos().add_wrapped(self.dir);
+        self.body.push_front(head);
+        if self.body.len() > self.len {
+            self.body.pop_back()
+        } else {
+            None
         }
-    }
-    pub fn advance(&mut self) {
-        self.head = self.head.add_wrapped(self.dir);
     }
     pub fn add_food(&mut self, food: usize) {
         self.len += food;
     }
-    pub fn point(&mut self, dir: Dir) {
+    /// Shortens the snake to `len`, returning the cells that were dropped off the tail
+    pub fn shrink_to(&mut self, len: usize) -> Vec<Coord> {
+        self.len = len.max(1).min(self.len);
+        let keep = self.len.min(self.body.len());
+        self.body.drain(keep..).collect()
+    }
+    /// Bites off the body from wherever the head ran into it. Returns the cells bitten off
+    pub fn bite_tail(&mut self) -> Vec<Coord> {
+        let head = self.head_pos();
+        let bite = self.body.iter().skip(1).position(|&segment| segment == head);
+        match bite {
+            Some(i) => self.shrink_to(i + 1),
+            None => Vec::new(),
+        }
+    }
+    pub fn covers(&self, coord: Coord) -> bool {
+        self.body.contains(&coord)
+    }
+    /// Every cell the snake covers, head first
+    pub fn body(&self) -> impl Iterator<Item = Coord> + '_ {
+        self.body.iter().copied()
+    }
+    /// Every cell the snake covers, head first, with how it joins on to its neighbours
+    pub fn parts(&self) -> impl Iterator<Item = (Coord, SnakePart)> + '_ {
+        self.body().enumerate().map(move |(i, coord)| {
+            let front = match i {
+                0 => Some(self.dir),
+                _ => coord.dir_to(self.body[i - 1]),
+            };
+            let back = self.body.get(i + 1).and_then(|&next| coord.dir_to(next));
+            (coord, SnakePart { front, back })
+        })
+    }
+    /// Turns the snake unless it would reverse or keep going. Returns whether it turned
+    pub fn point(&mut self, dir: Dir) -> bool {
+        if dir == self.dir || dir.is_opposite(self.dir) {
+            return false;
+        }
         self.dir = dir;
+        true
     }
     pub fn len(&self) -> usize {
         self.len
     }
     pub fn head_pos(&self) -> Coord {
-        self.head
+        self.body[0]
+    }
+    pub fn dir(&self) -> Dir {
+        self.dir
     }
 }
 
+pub const START_SNAKE_LEN: usize = 5;
+
 pub const LOGIC_MAX_MSPT: u64 = 100;
 pub const DRAW_MAX_USPT: u128 = 1_000_000u128 / 60;
 
+/// The RNG for every random decision in a run, seeded from `GameState::seed`
+pub type GameRng = ChaCha12Rng;
+
 pub const SB_WIDTH: usize = 28;
 pub const SB_HEIGHT: usize = 100;
 
+pub const MAX_PLAYERS: usize = 2;
+/// The head, and the two alternating body colors, of each player's snake
+pub const PLAYER_SNAKE_COLORS: [[SnakeColor; 3]; MAX_PLAYERS] = [
+    [SnakeColor::Head, SnakeColor::DarkRed, SnakeColor::LightRed],
+    [SnakeColor::BlueHead, SnakeColor::DarkBlue, SnakeColor::LightBlue],
+];
+
+/// The head and body colors shared by every rival
+pub const RIVAL_SNAKE_COLORS: [SnakeColor; 3] = [SnakeColor::RivalHead, SnakeColor::RivalDark, SnakeColor::RivalLight];
+
+/// A snake and everything that belongs to whoever is steering it
+pub struct Player {
+    pub snake: Snake,
+    /// Shows the shared shop, time and score alongside this player's own coins and inventory
+    pub scoreboard: Board<SB_WIDTH, SB_HEIGHT>,
+    pub coins: usize,
+    /// Income from plants that hasn't added up to a whole coin yet
+    pub coin_progress: usize,
+    pub invinc_time: usize,
+    /// Elevation dug up with the shovel that the snake is carrying
+    pub dirt: usize,
+    /// How many of each powerup have been bought but not used, indexed by `PowerupType`
+    pub inventory: [usize; NUM_POWERUP_TYPES],
+}
+impl Player {
+    /// Where the snake of the player with this index starts each level
+    pub fn start(index: usize) -> Coord {
+        Coord { x: 5, y: 5 + 10 * index }
+    }
+}
+
+/// A computer-controlled snake that competes with the players for food and income
+pub struct Rival {
+    pub snake: Snake,
+    pub coins: usize,
+    /// Income from plants that hasn't added up to a whole coin yet
+    pub coin_progress: usize,
+}
+
 pub struct GameState {
     pub level: &'static Level,
     pub board: Board,
-    pub scoreboard: Board<SB_WIDTH, SB_HEIGHT>,
     pub shop: ShopState,
 
-    pub snake: Snake,
-    pub coins: usize,
-    pub invinc_time: usize,
+    /// Between one and `MAX_PLAYERS` players
+    pub players: Vec<Player>,
+    /// The rivals still alive in this level
+    pub rivals: Vec<Rival>,
+    /// The price multiplier that the next shop will be loaded with
+    pub price_multiplier: usize,
 
     pub failed: bool,
+    pub fail_reason: Option<FailReason>,
+    /// The number of living plants (seeds, burning or not) as of the last tick
+    pub seed_count: usize,
+    /// The number of ticks played in the current level
+    pub level_time: usize,
+    /// Set once the level is complete
+    pub final_score: Option<usize>,
     /// The frame number from logic's perspective
     pub frame_num: usize,
 
     pub debug_screen: bool,
     pub debug_info: DebugInfo,
 
+    /// The seed the run was started with
+    pub seed: u64,
+    /// Used for sequential random decisions (shop, explosions, weather, etc.)
+    pub rng: GameRng,
+    /// Mixed into the per-cell RNGs used while ticking the board
     pub salt: u32,
 }
 impl GameState {
@@ -471,17 +649,75 @@ impl GameState {
         self.reset_level()
     }
     pub fn reset_level(&mut self) -> Option<Box<dyn LevelState>> {
-        let l = (self.level.new_level_state)();
+        let mut l = (self.level.new_level_state)(&mut self.rng);
+        self.shop = l.new_shop(&mut self.rng);
+        self.price_multiplier = self.shop.price_multiplier;
     
         println!("Level {}: {}", self.level.index + 1, self.level.name);
         self.board = Board::from_bytes(self.level.raw_board);
-        let snake = Snake::new((5, 5), Dir::Right, self.snake.len());
-        
-        self.snake = snake;
+        // Each snake keeps its whole body, laid out behind where it starts
+        for i in 0..self.players.len() {
+            let free = |coord: Coord| {
+                let cell = self.board.cell_at(coord);
+                cell.obj == CellObject::None
+                    && !matches!(cell.floor, CellFloor::Lava { .. })
+                    && !self.players[..i].iter().any(|p| p.snake.covers(coord))
+            };
+            let snake = Snake::laid_out(Player::start(i), Dir::Right, self.players[i].snake.len(), free);
+            self.players[i].snake = snake;
+            self.players[i].dirt = 0;
+        }
+        spawn_rivals(self);
         self.failed = false;
+        self.fail_reason = None;
+        self.seed_count = 0;
+        self.final_score = None;
+        self.level_time = 0;
+        self.redraw_scoreboards();
 
         Some(l)
     }
+    /// Draws everything the scoreboards show onto fresh banners
+    pub fn redraw_scoreboards(&mut self) {
+        let secs_left = self.time_left_secs();
+        for p in &mut self.players {
+            p.scoreboard = Board::from_bytes(SCORE_BANNER_VERT);
+            p.scoreboard.shop(&self.shop);
+            p.scoreboard.pm(self.price_multiplier);
+            p.scoreboard.coins(p.coins);
+            p.scoreboard.time(secs_left);
+            p.scoreboard.dirt(p.dirt);
+            p.scoreboard.inventory(&p.inventory);
+            if let Some(score) = self.final_score {
+                p.scoreboard.score(score);
+            }
+        }
+    }
+    /// The player that actions meant for `player` control, falling back to the first
+    pub fn player_index(&self, player: usize) -> usize {
+        if player < self.players.len() { player } else { 0 }
+    }
+    /// The time left in the level, rounded up to the second
+    pub fn time_left_secs(&self) -> usize {
+        let ticks_left = self.level.time_limit.saturating_sub(self.level_time);
+        (ticks_left * LOGIC_MAX_MSPT as usize + 999) / 1000
+    }
+}
+
+#[derive(Clone, Copy, Hash, PartialEq, Debug)]
+pub enum FailReason {
+    HitWallOrLava,
+    HitBorder,
+    LastSeedDied,
+}
+impl FailReason {
+    pub fn message(&self) -> &'static str {
+        match self {
+            FailReason::HitWallOrLava => "Hit wall or lava!",
+            FailReason::HitBorder => "Hit border!",
+            FailReason::LastSeedDied => "The last seed died!",
+        }
+    }
 }
 
 pub const NUM_SHOP_ITEMS: usize = 3;
@@ -589,3 +825,31 @@ pub fn proc_array<T, F: FnMut(usize) -> T, const N: usize>(mut gen: F) -> [T; N]
         item_copy
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn laid_out_snake_folds_back_at_a_blocked_cell() {
+        let snake = Snake::laid_out(Coord { x: 3, y: 5 }, Dir::Right, 6, |coord| coord.x >= 1);
+        let body: Vec<Coord> = snake.body().collect();
+        let expected = [(3, 5), (2, 5), (1, 5), (1, 6), (2, 6), (3, 6)].map(|(x, y)| Coord { x, y });
+        assert_eq!(body, expected);
+        let parts: Vec<SnakePart> = snake.parts().map(|(_, part)| part).collect();
+        assert_eq!(parts[0], SnakePart { front: Some(Dir::Right), back: Some(Dir::Left) });
+        assert_eq!(parts[2], SnakePart { front: Some(Dir::Right), back: Some(Dir::Down) });
+        assert_eq!(parts[5], SnakePart { front: Some(Dir::Left), back: None });
+    }
+
+    #[test]
+    fn biting_the_body_cuts_off_the_tail() {
+        // A snake curled up so that turning down runs into its own body
+        let body = [(2, 1), (1, 1), (1, 2), (2, 2), (3, 2), (4, 2)].map(|(x, y)| Coord { x, y });
+        let mut snake = Snake::with_body(body.into(), Dir::Down, 6);
+        assert_eq!(snake.advance(), Some(Coord { x: 4, y: 2 }));
+        assert_eq!(snake.bite_tail(), [(2, 2), (3, 2)].map(|(x, y)| Coord { x, y }));
+        assert_eq!(snake.len(), 4);
+        assert_eq!(snake.bite_tail(), []);
+    }
+}